@@ -0,0 +1,307 @@
+//! The `kinesis-layout` command-line tool, built with the `config` feature. It makes a layout
+//! from a config file and/or a preset with overrides, validates it and writes it out, either
+//! once or every time the config file is saved, or checks whether a v-drive still holds it.
+
+extern crate kinesis_layout;
+
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+use kinesis_layout::configure::Configure;
+use kinesis_layout::keys::{self, *};
+use kinesis_layout::layout::{preset, Layout};
+use kinesis_layout::macros::System;
+use kinesis_layout::merge::MergePolicy;
+use kinesis_layout::parse::ParseErrorKind;
+use kinesis_layout::validate::Limits;
+use kinesis_layout::vdrive::{layout_path, write_file, LayoutSlot};
+
+const USAGE: &str = "\
+usage: kinesis-layout build [options]
+       kinesis-layout watch --config <file> [options]
+       kinesis-layout status --vdrive <dir> [options]
+
+build makes a layout, validates it and writes it to stdout, a file or a v-drive.
+watch rebuilds the layout every time the config file changes, shows how it
+changed and rewrites the output only when the layout changed.
+status compares the layout with the one in a v-drive slot, listing the
+remappings and macros which differ, e.g., after on-board programming. It exits
+with status 1 unless the two are in sync.
+
+options:
+    --config <file>       read a TOML config file
+    --preset <name>       start from a preset's remappings, e.g., colemak, which
+                          the config file and --remap override
+    --system <system>     pc, windows or mac
+    --remap <key>=<key>   remap a key using layout file tokens, e.g., A=left,
+                          kp-enter=kp0, caps=mute or `=null for a dead key
+    --output <file>       write the layout to a file
+    --vdrive <dir>        write the layout into a mounted v-drive
+    --slot <slot>         the v-drive slot: 1 to 9 (the default), qwerty or dvorak
+    --interval <ms>       how often watch checks the config file (default 500)
+    --help                print this message";
+
+/// Where a built layout is written.
+enum Target {
+    Stdout,
+    File(PathBuf),
+    VDrive(PathBuf, LayoutSlot),
+}
+
+struct Options {
+    config: Option<PathBuf>,
+    preset: Option<String>,
+    system: Option<System>,
+    remaps: Vec<(KeyLayer, Option<keys::Target>)>,
+    target: Target,
+    interval: Duration,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result = match args.split_first() {
+        Some((command, rest)) if command == "build" => parse_options(rest).and_then(|o| build(&o)),
+        Some((command, rest)) if command == "watch" => parse_options(rest).and_then(|o| watch(&o)),
+        Some((command, rest)) if command == "status" => {
+            parse_options(rest).and_then(|o| status(&o))
+        }
+        Some((command, _)) if command == "help" || command == "--help" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(USAGE.into()),
+    };
+
+    if let Err(error) = result {
+        eprintln!("error: {}", error);
+        process::exit(1);
+    }
+}
+
+fn parse_options(args: &[String]) -> Result<Options, Box<dyn Error>> {
+    let mut options = Options {
+        config: None,
+        preset: None,
+        system: None,
+        remaps: Vec::new(),
+        target: Target::Stdout,
+        interval: Duration::from_millis(500),
+    };
+    let mut output = None;
+    let mut vdrive = None;
+    let mut slot = LayoutSlot::Custom(1);
+
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if arg == "--help" {
+            println!("{}", USAGE);
+            process::exit(0);
+        }
+
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {}", arg))?;
+
+        match arg.as_str() {
+            "--config" => options.config = Some(PathBuf::from(value)),
+            "--preset" => options.preset = Some(value.clone()),
+            "--system" => options.system = Some(parse_system(value)?),
+            "--remap" => options.remaps.push(parse_remap(value)?),
+            "--output" => output = Some(PathBuf::from(value)),
+            "--vdrive" => vdrive = Some(PathBuf::from(value)),
+            "--slot" => {
+                slot = LayoutSlot::from_file_name(&format!("{}.txt", value))
+                    .ok_or_else(|| format!("invalid slot `{}`", value))?
+            }
+            "--interval" => match value.parse()? {
+                0 => return Err("--interval must be at least 1".into()),
+                ms => options.interval = Duration::from_millis(ms),
+            },
+            _ => return Err(format!("unknown option {}\n\n{}", arg, USAGE).into()),
+        }
+    }
+
+    options.target = match (output, vdrive) {
+        (Some(_), Some(_)) => return Err("--output and --vdrive cannot be used together".into()),
+        (Some(path), None) => Target::File(path),
+        (None, Some(root)) => Target::VDrive(root, slot),
+        (None, None) => Target::Stdout,
+    };
+
+    Ok(options)
+}
+
+fn parse_system(value: &str) -> Result<System, String> {
+    match value.to_lowercase().as_str() {
+        "pc" => Ok(System::PC),
+        "windows" => Ok(System::Windows),
+        "mac" => Ok(System::Mac),
+        _ => Err(format!("invalid system `{}`", value)),
+    }
+}
+
+fn parse_remap(value: &str) -> Result<(KeyLayer, Option<keys::Target>), Box<dyn Error>> {
+    let (key, new_key) = value
+        .split_once('=')
+        .ok_or_else(|| format!("invalid remapping `{}`", value))?;
+
+    let new_key = if new_key.eq_ignore_ascii_case("null") {
+        None
+    } else {
+        Some(new_key.parse()?)
+    };
+
+    match key.parse()? {
+        keys::Target::Key(key) => Ok((key, new_key)),
+        keys::Target::Action(action) => Err(ParseErrorKind::RemappedAction(action).into()),
+    }
+}
+
+/// Makes the layout described by `options` and checks it against the Advantage 2's limits. The
+/// config file's remappings override the preset's.
+fn make_layout(options: &Options) -> Result<Layout, Box<dyn Error>> {
+    let mut configure = Configure::new();
+
+    if let Some(name) = &options.preset {
+        configure
+            .with_remappings(preset(name).ok_or_else(|| format!("unknown preset `{}`", name))?);
+    }
+
+    if let Some(path) = &options.config {
+        let text =
+            fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+        let config = Configure::from_toml(&text)
+            .map_err(|error| format!("{}: {}", path.display(), error))?;
+
+        configure = configure.merge(&config, MergePolicy::PreferRight)?.merged;
+    }
+
+    if let Some(system) = options.system {
+        configure.set_system(system);
+    }
+
+    for (key, new_key) in options.remaps.iter() {
+        match (new_key, key.keypad_state()) {
+            (Some(new_key), _) => configure.remap_permissive(key.clone(), new_key.clone()),
+            (None, Keypad::Off) => configure.dead_key(key.key().clone()),
+            (None, Keypad::On) => configure.keypad_dead_key(key.key().clone()),
+        };
+    }
+
+    let layout = configure.make()?;
+    let diagnostics = layout.validate(&Limits::default());
+
+    if !diagnostics.is_empty() {
+        let diagnostics: Vec<String> = diagnostics.iter().map(|d| format!("{}", d)).collect();
+        return Err(diagnostics.join("\n").into());
+    }
+
+    Ok(layout)
+}
+
+fn build(options: &Options) -> Result<(), Box<dyn Error>> {
+    write_layout(&options.target, &make_layout(options)?)
+}
+
+fn write_layout(target: &Target, layout: &Layout) -> Result<(), Box<dyn Error>> {
+    match target {
+        Target::Stdout => println!("{}", layout),
+        Target::File(path) => write_file(path, &format!("{}", layout))?,
+        Target::VDrive(root, slot) => {
+            let path = layout.write_to_vdrive(root, *slot)?;
+            eprintln!("wrote {}", path.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// The layout currently held by `target`. An empty layout is returned if there is none yet.
+fn read_layout(target: &Target) -> Result<Layout, Box<dyn Error>> {
+    let path = match target {
+        Target::Stdout => return Ok(Layout::default()),
+        Target::File(path) => path.clone(),
+        Target::VDrive(root, slot) => layout_path(root, *slot),
+    };
+
+    if !path.exists() {
+        return Ok(Layout::default());
+    }
+
+    Layout::parse(&fs::read_to_string(&path)?)
+        .map_err(|error| format!("{}: {}", path.display(), error).into())
+}
+
+/// Rebuilds the layout whenever the config file's contents change. Errors in the config file
+/// and failed writes are reported without stopping, and a failed write is retried.
+fn watch(options: &Options) -> Result<(), Box<dyn Error>> {
+    let config = options.config.as_ref().ok_or("watch requires --config")?;
+
+    let mut previous = read_layout(&options.target)?;
+    let mut last_contents = None;
+
+    loop {
+        let contents = fs::read_to_string(config).ok();
+
+        if last_contents.as_ref() != Some(&contents) {
+            let handled = match make_layout(options) {
+                Ok(layout) => {
+                    let diff = previous.diff(&layout);
+
+                    if diff.is_empty() {
+                        eprintln!("layout unchanged");
+                        true
+                    } else {
+                        eprintln!("{}", diff);
+
+                        match write_layout(&options.target, &layout) {
+                            Ok(()) => {
+                                previous = layout;
+                                true
+                            }
+                            Err(error) => {
+                                eprintln!("error: {}", error);
+                                false
+                            }
+                        }
+                    }
+                }
+                Err(error) => {
+                    eprintln!("error: {}", error);
+                    true
+                }
+            };
+
+            // A failed write is retried on the next check, e.g., once the v-drive is mounted.
+            if handled {
+                last_contents = Some(contents);
+            }
+        }
+
+        thread::sleep(options.interval);
+    }
+}
+
+/// Prints whether the v-drive slot holds the layout, exiting with status 1 if it does not.
+fn status(options: &Options) -> Result<(), Box<dyn Error>> {
+    let (root, slot) = match &options.target {
+        Target::VDrive(root, slot) => (root, *slot),
+        _ => return Err("status requires --vdrive".into()),
+    };
+
+    let status = make_layout(options)?.status(root, slot)?;
+    println!("{}", status);
+
+    if !status.is_in_sync() {
+        process::exit(1);
+    }
+
+    Ok(())
+}
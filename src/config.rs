@@ -0,0 +1,384 @@
+//! A TOML file format for `Configure`, enabled by the `config` feature. Keys, shortcuts and
+//! macro triggers are written using the tokens of the layout file format.
+//!
+//! ```toml
+//! system = "mac"           # "pc" (the default), "windows" or "mac"
+//! preset = "colemak"       # remappings to start from
+//! invert_numbers = true    # see `Configure::invert_numbers`
+//!
+//! [top]                    # the top layer; `[keypad]` takes the same settings
+//! remap = { A = "left" }   # `Configure::remap`; a key may also be remapped to an `Action`
+//! dead_keys = ["`"]        # `Configure::dead_key`
+//! remove = ["T"]           # removes a preset's remapping, see `Configure::remove_remap`
+//! invert = ["hyphen"]      # `Configure::invert_key`
+//!
+//! [cross_layer]            # `Configure::remap_permissive`, naming the layer of both keys
+//! "kp-enter" = "space"
+//!
+//! [keypad_keys]            # `Configure::keypad_shift` and `Configure::keypad_toggle`
+//! shift = ["rwin"]
+//! toggle = ["caps"]
+//!
+//! [[macro]]
+//! trigger = "{kp-c}"
+//! speed = 3                # `MacroBuilder::speed`, from 1 to 9
+//! output = [
+//!     { text = "www.test.com" },
+//!     { shortcut = "{lctrl}{c}" },
+//!     { action = "vol+" },     # an `Action`, e.g., "mute" or "lmouse"
+//!     { command = "copy" },    # a `Command`, e.g., "jump_forward"
+//!     { left = 3 },            # cursor moves: `up`, `down`, `left` or `right`
+//!     { delay = 250 },         # a pause in milliseconds, from 1 to 999
+//!     { press = "lshift" },    # holds a modifier until it is released
+//!     { down = 2 },
+//!     { release = "lshift" },
+//! ]
+//! ```
+//!
+//! The preset is applied first, so the file's remappings override it. Then, for the top layer and
+//! then the keypad layer, `remove`, `remap`, `dead_keys` and `invert` are applied, followed by
+//! `cross_layer`, `keypad_keys`, `invert_numbers` and the macros. A key remapped more than once,
+//! e.g., by both `remap` and `dead_keys`, and a trigger used by more than one macro, or by a macro
+//! and an inverted key, are errors. Keypad keys, e.g., `kp1`, are remapped in `[keypad]`, not in
+//! `[top]`.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::configure::{Configure, INVERTED_NUMBERS};
+use crate::keys::*;
+use crate::layout::preset;
+use crate::macros::*;
+use crate::parse::{ParseError, ParseErrorKind};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    #[serde(default)]
+    system: System,
+    preset: Option<Spanned<String>>,
+    #[serde(default)]
+    invert_numbers: Option<Spanned<bool>>,
+    #[serde(default)]
+    top: LayerConfig,
+    #[serde(default)]
+    keypad: LayerConfig,
+    #[serde(default)]
+    cross_layer: BTreeMap<Spanned<String>, Spanned<String>>,
+    #[serde(default)]
+    keypad_keys: LayerKeys,
+    #[serde(default, rename = "macro")]
+    macros: Vec<MacroConfig>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct LayerConfig {
+    #[serde(default)]
+    remap: BTreeMap<Spanned<String>, Spanned<String>>,
+    #[serde(default)]
+    dead_keys: Vec<Spanned<String>>,
+    #[serde(default)]
+    remove: Vec<Spanned<String>>,
+    #[serde(default)]
+    invert: Vec<Spanned<String>>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct LayerKeys {
+    #[serde(default)]
+    shift: Vec<Spanned<String>>,
+    #[serde(default)]
+    toggle: Vec<Spanned<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MacroConfig {
+    trigger: Spanned<String>,
+    speed: Option<Spanned<u8>>,
+    output: Vec<Spanned<Step>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Step {
+    Text(String),
+    Shortcut(String),
+    Action(Action),
+    Command(Command),
+    Up(usize),
+    Down(usize),
+    Left(usize),
+    Right(usize),
+    Delay(u16),
+    Press(Modifier),
+    Release(Modifier),
+}
+
+/// Parses a config file into a `Configure` value. Errors report the line they occurred on.
+pub fn parse_config(s: &str) -> Result<Configure, ParseError> {
+    let file: ConfigFile = toml::from_str(s).map_err(|error| ParseError {
+        line: error.span().map_or(1, |span| line(s, span.start)),
+        kind: ParseErrorKind::InvalidConfig(error.message().to_string()),
+    })?;
+
+    let mut configure = Configure::new();
+    configure.set_system(file.system);
+
+    let mut remapped = BTreeSet::new();
+    let mut define = |key: KeyLayer, spanned: &Spanned<String>| {
+        if remapped.insert(key.clone()) {
+            Ok(())
+        } else {
+            Err(ParseError {
+                line: line(s, spanned.span().start),
+                kind: ParseErrorKind::DuplicateRemapping(key),
+            })
+        }
+    };
+
+    let mut triggers = BTreeSet::new();
+    let mut trigger = |shortcut: Shortcut, offset: usize| {
+        if triggers.insert(shortcut.clone()) {
+            Ok(())
+        } else {
+            Err(ParseError {
+                line: line(s, offset),
+                kind: ParseErrorKind::DuplicateMacro(shortcut),
+            })
+        }
+    };
+
+    if let Some(name) = file.preset {
+        let remappings = preset(name.get_ref()).ok_or_else(|| ParseError {
+            line: line(s, name.span().start),
+            kind: ParseErrorKind::InvalidSetting("preset".to_string(), name.get_ref().clone()),
+        })?;
+
+        configure.with_remappings(remappings);
+    }
+
+    for (keypad, layer) in [(Keypad::Off, &file.top), (Keypad::On, &file.keypad)] {
+        for spanned in layer.remove.iter() {
+            let key = layer_key(s, keypad, spanned)?;
+
+            match keypad {
+                Keypad::Off => configure.remove_remap(key),
+                Keypad::On => configure.remove_remap_keypad(key),
+            };
+        }
+
+        for (old_key, new_key) in layer.remap.iter() {
+            let key = layer_key(s, keypad, old_key)?;
+            define(KeyLayer::new(keypad, key.clone()), old_key)?;
+
+            match keypad {
+                Keypad::Off => configure.remap(key, target(s, new_key)?),
+                Keypad::On => configure.remap_keypad(key, target(s, new_key)?),
+            };
+        }
+
+        for spanned in layer.dead_keys.iter() {
+            let key = layer_key(s, keypad, spanned)?;
+            define(KeyLayer::new(keypad, key.clone()), spanned)?;
+
+            match keypad {
+                Keypad::Off => configure.dead_key(key),
+                Keypad::On => configure.keypad_dead_key(key),
+            };
+        }
+
+        for spanned in layer.invert.iter() {
+            let key: NonModifier = token(s, spanned)?;
+
+            for shortcut in inverted(keypad, key) {
+                trigger(shortcut, spanned.span().start)?;
+            }
+
+            match keypad {
+                Keypad::Off => configure.invert_key(key),
+                Keypad::On => configure.invert_keypad_key(key),
+            };
+        }
+    }
+
+    for (old_key, new_key) in file.cross_layer.iter() {
+        let key: KeyLayer = source(s, old_key)?;
+        define(key.clone(), old_key)?;
+        configure.remap_permissive(key, token::<Target>(s, new_key)?);
+    }
+
+    for spanned in file.keypad_keys.shift.iter() {
+        let key: Key = source(s, spanned)?;
+        define(KeyLayer::off(key.clone()), spanned)?;
+        define(KeyLayer::on(key.clone()), spanned)?;
+        configure.keypad_shift(key);
+    }
+
+    for spanned in file.keypad_keys.toggle.iter() {
+        let key: Key = source(s, spanned)?;
+        define(KeyLayer::off(key.clone()), spanned)?;
+        define(KeyLayer::on(key.clone()), spanned)?;
+        configure.keypad_toggle(key);
+    }
+
+    if let Some(invert_numbers) = file.invert_numbers.filter(|invert| *invert.get_ref()) {
+        for key in INVERTED_NUMBERS.iter() {
+            for shortcut in inverted(Keypad::Off, *key) {
+                trigger(shortcut, invert_numbers.span().start)?;
+            }
+        }
+
+        configure.invert_numbers();
+    }
+
+    for macro_config in file.macros.iter() {
+        let shortcut: Shortcut = token(s, &macro_config.trigger)?;
+        trigger(shortcut.clone(), macro_config.trigger.span().start)?;
+
+        let mut builder = MacroBuilder::new();
+
+        if let Some(speed) = &macro_config.speed {
+            builder.speed(*speed.get_ref()).map_err(|e| ParseError {
+                line: line(s, speed.span().start),
+                kind: ParseErrorKind::InvalidMacro(e),
+            })?;
+        }
+
+        for step in macro_config.output.iter() {
+            let error = |kind| ParseError {
+                line: line(s, step.span().start),
+                kind,
+            };
+
+            match step.get_ref() {
+                Step::Text(text) => {
+                    builder
+                        .with_string(text)
+                        .map_err(|e| error(ParseErrorKind::InvalidMacro(e)))?;
+                }
+                Step::Shortcut(shortcut) => {
+                    builder.with_shortcut(Shortcut::from_str(shortcut).map_err(error)?);
+                }
+                Step::Action(action) => {
+                    builder.with_action(*action);
+                }
+                Step::Command(command) => {
+                    builder.with_command(*command);
+                }
+                Step::Up(n) => {
+                    builder.cursor_up(*n);
+                }
+                Step::Down(n) => {
+                    builder.cursor_down(*n);
+                }
+                Step::Left(n) => {
+                    builder.cursor_left(*n);
+                }
+                Step::Right(n) => {
+                    builder.cursor_right(*n);
+                }
+                Step::Delay(ms) => {
+                    builder
+                        .delay(*ms)
+                        .map_err(|e| error(ParseErrorKind::InvalidMacro(e)))?;
+                }
+                Step::Press(modifier) => {
+                    builder.press(*modifier);
+                }
+                Step::Release(modifier) => {
+                    builder.release(*modifier);
+                }
+            }
+        }
+
+        let macro_output = builder.make();
+
+        macro_output
+            .check_held_modifiers()
+            .map_err(|e| ParseError {
+                line: line(s, macro_config.trigger.span().start),
+                kind: ParseErrorKind::InvalidMacro(e),
+            })?;
+
+        configure.insert_macro(shortcut, Ok(macro_output));
+    }
+
+    Ok(configure)
+}
+
+/// Parses a key, key layer or shortcut token, reporting the line it is on.
+fn token<T>(s: &str, spanned: &Spanned<String>) -> Result<T, ParseError>
+where
+    T: FromStr<Err = ParseErrorKind>,
+{
+    spanned.get_ref().parse().map_err(|kind| ParseError {
+        line: line(s, spanned.span().start),
+        kind,
+    })
+}
+
+/// Parses a key or key layer token naming a key to remap. An action is reported as such rather
+/// than as an unknown token.
+fn source<T>(s: &str, spanned: &Spanned<String>) -> Result<T, ParseError>
+where
+    T: FromStr<Err = ParseErrorKind>,
+{
+    token(s, spanned).map_err(|error| match spanned.get_ref().parse() {
+        Ok(action) => ParseError {
+            kind: ParseErrorKind::RemappedAction(action),
+            ..error
+        },
+        Err(_) => error,
+    })
+}
+
+/// Parses what a key of the `[top]` or `[keypad]` table is remapped to: a key, which is put in
+/// the table's layer, or an action.
+fn target(s: &str, spanned: &Spanned<String>) -> Result<Target, ParseError> {
+    token::<Key>(s, spanned).map(Target::from).or_else(|error| {
+        spanned
+            .get_ref()
+            .parse()
+            .map(Target::Action)
+            .map_err(|_| error)
+    })
+}
+
+/// Parses a key of the `[top]` or `[keypad]` table. A keypad key is rejected in `[top]` rather
+/// than being moved to the keypad layer.
+fn layer_key(s: &str, keypad: Keypad, spanned: &Spanned<String>) -> Result<Key, ParseError> {
+    match source(s, spanned)? {
+        Key::Keypad(key) if keypad == Keypad::Off => Err(ParseError {
+            line: line(s, spanned.span().start),
+            kind: ParseErrorKind::InvalidConfig(format!(
+                "keypad key `{}` in [top]; remap it in [keypad]",
+                key
+            )),
+        }),
+        key => Ok(key),
+    }
+}
+
+/// The triggers of the two macros inverting `key` in a layer, see `Configure::invert_key`.
+fn inverted(keypad: Keypad, key: NonModifier) -> [Shortcut; 2] {
+    let shortcut = |modifiers| match keypad {
+        Keypad::Off => Shortcut::keypad_off(modifiers, key),
+        Keypad::On => Shortcut::keypad_on(modifiers, key),
+    };
+
+    [
+        shortcut(BTreeSet::new()),
+        shortcut(btreeset! {Modifier::RightShift}),
+    ]
+}
+
+/// The one-based line of the byte at `offset`.
+fn line(s: &str, offset: usize) -> usize {
+    s[..offset.min(s.len())].matches('\n').count() + 1
+}
@@ -1,18 +1,110 @@
+use std::collections::BTreeMap;
 use std::collections::BTreeSet;
 use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
 
+#[cfg(feature = "config")]
+use crate::config::parse_config;
 use crate::keys::*;
 use crate::layout::Layout;
 use crate::macros::*;
+use crate::merge::{finish, merge_maps, Conflict, MergeError, MergePolicy, Merged};
+#[cfg(feature = "config")]
+use crate::parse::ParseError;
+use crate::vdrive::LayoutSlot;
 
-/// `Configure` is used with the builder pattern to configure a keyboard `Layout`.
+/// `Configure` is used with the builder pattern to configure a keyboard `Layout`. A value with a
+/// macro which could not be built cannot be serialized, and deserialized macros are checked as by
+/// `with_macro`.
 #[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(from = "ConfigureData")
+)]
 pub struct Configure {
     system: System,
-    remappings: HashMap<KeyLayer, Option<KeyLayer>>,
+    remappings: HashMap<KeyLayer, Option<Target>>,
     macros: HashMap<Shortcut, MacroOutputTemp>,
+    macro_errors: BTreeMap<Shortcut, MacroError>,
 }
 
+/// The serialized form of a `Configure` value.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ConfigureData {
+    system: System,
+    #[serde(serialize_with = "crate::serialize::sorted")]
+    remappings: HashMap<KeyLayer, Option<Target>>,
+    #[serde(serialize_with = "crate::serialize::sorted")]
+    macros: HashMap<Shortcut, MacroOutputTemp>,
+}
+
+#[cfg(feature = "serde")]
+impl From<ConfigureData> for Configure {
+    fn from(data: ConfigureData) -> Self {
+        let mut configure = Configure {
+            system: data.system,
+            remappings: data.remappings,
+            ..Default::default()
+        };
+
+        for (shortcut, macro_output) in data.macros {
+            configure.with_macro(shortcut, macro_output);
+        }
+
+        configure
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Configure {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Some((shortcut, error)) = self.macro_errors.iter().next() {
+            return Err(serde::ser::Error::custom(ConfigureError::Macro(
+                shortcut.clone(),
+                error.clone(),
+            )));
+        }
+
+        ConfigureData {
+            system: self.system,
+            remappings: self.remappings.clone(),
+            macros: self.macros.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// The number keys inverted by `Configure::invert_numbers`.
+pub(crate) const INVERTED_NUMBERS: [NonModifier; 9] = {
+    use self::NonModifier::*;
+
+    [One, Two, Three, Four, Five, Six, Seven, Eight, Nine]
+};
+
+/// An error which prevents a `Configure` value from being made into a `Layout`.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Hash, Debug)]
+pub enum ConfigureError {
+    /// The macro for a shortcut could not be built.
+    Macro(Shortcut, MacroError),
+}
+
+impl fmt::Display for ConfigureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigureError::Macro(shortcut, error) => {
+                write!(f, "macro for {}: {}", shortcut, error)
+            }
+        }
+    }
+}
+
+impl error::Error for ConfigureError {}
+
 impl Configure {
     /// Creates a new `Configure` value  with `system` set to `PC` and
     /// no key remappings or macros.
@@ -20,28 +112,56 @@ impl Configure {
         Default::default()
     }
 
+    /// Creates a `Configure` value from an existing `Layout` so that it can be edited further.
+    /// `system` is set to `PC` as a `Layout` does not record the system it was made for; the
+    /// remappings added for a `Mac` by `make` are already part of the layout's remappings.
+    pub fn from_layout(layout: &Layout) -> Configure {
+        Configure {
+            system: System::default(),
+            remappings: layout.remappings.clone(),
+            macros: layout
+                .macros
+                .iter()
+                .map(|(k, v)| (k.clone(), v.to_macro_output_temp()))
+                .collect(),
+            macro_errors: BTreeMap::new(),
+        }
+    }
+
+    /// Creates a `Configure` value from a config file. See the `config` module for the format.
+    /// Errors report the line they occurred on.
+    #[cfg(feature = "config")]
+    pub fn from_toml(s: &str) -> Result<Configure, ParseError> {
+        parse_config(s)
+    }
+
     /// Used to set the system to either `PC` or `Mac`.
     pub fn set_system(&mut self, system: System) -> &mut Configure {
         self.system = system;
         self
     }
 
-    /// Used to remap a single key.
-    pub fn remap(&mut self, old_key: Key, new_key: Key) -> &mut Configure {
-        self.remappings
-            .insert(KeyLayer::off(old_key), Some(KeyLayer::off(new_key)));
+    /// Used to remap a single key, either to another key or to an `Action`.
+    pub fn remap(&mut self, old_key: Key, new_key: impl Into<Target>) -> &mut Configure {
+        self.remappings.insert(
+            KeyLayer::off(old_key),
+            Some(new_key.into().in_layer(Keypad::Off)),
+        );
         self
     }
 
     /// Used to remap a single key in the keypad layer.
-    pub fn remap_keypad(&mut self, old_key: Key, new_key: Key) -> &mut Configure {
-        self.remappings
-            .insert(KeyLayer::on(old_key), Some(KeyLayer::on(new_key)));
+    pub fn remap_keypad(&mut self, old_key: Key, new_key: impl Into<Target>) -> &mut Configure {
+        self.remappings.insert(
+            KeyLayer::on(old_key),
+            Some(new_key.into().in_layer(Keypad::On)),
+        );
         self
     }
 
     /// Used to remap a single key in both the top and keypad layers.
-    pub fn remap_all(&mut self, old_key: Key, new_key: Key) -> &mut Configure {
+    pub fn remap_all(&mut self, old_key: Key, new_key: impl Into<Target>) -> &mut Configure {
+        let new_key = new_key.into();
         self.remap(old_key.clone(), new_key.clone());
         self.remap_keypad(old_key, new_key);
         self
@@ -50,8 +170,12 @@ impl Configure {
     /// Used to remap a single key. This method allows a key to be mapped between the top
     /// and keypad layers. If this functionality is not required, preferred the use of
     /// `remap`, `remap_keypad` and `remap_all` methods.
-    pub fn remap_permissive(&mut self, old_key: KeyLayer, new_key: KeyLayer) -> &mut Configure {
-        self.remappings.insert(old_key, Some(new_key));
+    pub fn remap_permissive(
+        &mut self,
+        old_key: KeyLayer,
+        new_key: impl Into<Target>,
+    ) -> &mut Configure {
+        self.remappings.insert(old_key, Some(new_key.into()));
         self
     }
 
@@ -59,12 +183,68 @@ impl Configure {
     /// `Layout` using an alternative keyboard layout such as Dvorak or Colemak.
     pub fn with_remappings(
         &mut self,
-        remappings: HashMap<KeyLayer, Option<KeyLayer>>,
+        remappings: HashMap<KeyLayer, Option<Target>>,
     ) -> &mut Configure {
         self.remappings.extend(remappings);
         self
     }
 
+    /// Overlays the remappings and macros of `other` on this configuration. Remappings and
+    /// macros defined differently by both, and a differing `system`, are resolved using `policy`
+    /// and reported. Unlike `with_remappings`, conflicts are never silently overwritten.
+    pub fn merge(
+        &self,
+        other: &Configure,
+        policy: MergePolicy,
+    ) -> Result<Merged<Configure>, MergeError> {
+        let (remappings, remapping_conflicts) =
+            merge_maps(&self.remappings, &other.remappings, policy);
+        let (macros, macro_conflicts) =
+            merge_maps(&self.built_macros(), &other.built_macros(), policy);
+
+        let mut conflicts: Vec<Conflict> = remapping_conflicts
+            .into_iter()
+            .map(Conflict::Remapping)
+            .chain(macro_conflicts.into_iter().map(Conflict::Macro))
+            .collect();
+
+        let system = if self.system == other.system {
+            self.system
+        } else {
+            conflicts.push(Conflict::System);
+
+            match policy {
+                MergePolicy::PreferRight => other.system,
+                _ => self.system,
+            }
+        };
+
+        let mut merged = Configure {
+            system,
+            remappings,
+            ..Default::default()
+        };
+
+        for (shortcut, macro_output) in macros {
+            merged.try_with_macro(shortcut, macro_output);
+        }
+
+        finish(merged, conflicts, policy)
+    }
+
+    /// The macros and the errors building them, keyed by shortcut, so that both are merged alike.
+    fn built_macros(&self) -> HashMap<Shortcut, Result<MacroOutputTemp, MacroError>> {
+        self.macros
+            .iter()
+            .map(|(k, v)| (k.clone(), Ok(v.clone())))
+            .chain(
+                self.macro_errors
+                    .iter()
+                    .map(|(k, e)| (k.clone(), Err(e.clone()))),
+            )
+            .collect()
+    }
+
     /// Creates a dead key.
     pub fn dead_key(&mut self, key: Key) -> &mut Configure {
         self.remappings.insert(KeyLayer::off(key), None);
@@ -77,6 +257,18 @@ impl Configure {
         self
     }
 
+    /// Makes a key switch to the keypad layer while held, like the keypad key of a foot pedal.
+    /// The key is remapped in both layers so that it behaves the same in the keypad layer.
+    pub fn keypad_shift(&mut self, key: Key) -> &mut Configure {
+        self.remap_all(key, Action::KeypadShift)
+    }
+
+    /// Makes a key switch between the top and keypad layers, like the keypad key. The key is
+    /// remapped in both layers so that pressing it again in the keypad layer switches back.
+    pub fn keypad_toggle(&mut self, key: Key) -> &mut Configure {
+        self.remap_all(key, Action::KeypadToggle)
+    }
+
     /// Removes a remapping from the configuration. This method may be useful if, for example,
     /// you want to use an alternative keyboard layout such as Dvorak using the `with_remappings`
     /// method but want to remove a remapping.
@@ -94,13 +286,45 @@ impl Configure {
     }
 
     /// Used to add a macro to the keyboard layout. A macro requires a keypad shortcut and output
-    /// upon triggering the keypad shortcut.
+    /// upon triggering the keypad shortcut. A modifier pressed without being released is reported
+    /// by `make`.
     pub fn with_macro(
         &mut self,
         shortcut: Shortcut,
         macro_output: MacroOutputTemp,
     ) -> &mut Configure {
-        self.macros.insert(shortcut, macro_output);
+        self.try_with_macro(shortcut, Ok(macro_output))
+    }
+
+    /// Adds the result of building a macro, e.g., with `MacroBuilder::from_string`, so that an
+    /// error is reported by `make` rather than where the macro is built.
+    pub fn try_with_macro(
+        &mut self,
+        shortcut: Shortcut,
+        macro_output: Result<MacroOutputTemp, MacroError>,
+    ) -> &mut Configure {
+        self.insert_macro(
+            shortcut,
+            macro_output.and_then(|m| m.check_held_modifiers().map(|_| m)),
+        )
+    }
+
+    /// Adds the result of building a macro whose held modifiers have already been checked.
+    pub(crate) fn insert_macro(
+        &mut self,
+        shortcut: Shortcut,
+        macro_output: Result<MacroOutputTemp, MacroError>,
+    ) -> &mut Configure {
+        match macro_output {
+            Ok(macro_output) => {
+                self.macro_errors.remove(&shortcut);
+                self.macros.insert(shortcut, macro_output);
+            }
+            Err(error) => {
+                self.macros.remove(&shortcut);
+                self.macro_errors.insert(shortcut, error);
+            }
+        }
         self
     }
 
@@ -143,42 +367,54 @@ impl Configure {
     /// Inverts the number keys (0-9) so that accessing symbols does not require holding shift.
     /// May be useful for programmers who make heavy use of symbols.
     pub fn invert_numbers(&mut self) -> &mut Configure {
-        use self::NonModifier::*;
-
-        self.invert_key(One);
-        self.invert_key(Two);
-        self.invert_key(Three);
-        self.invert_key(Four);
-        self.invert_key(Five);
-        self.invert_key(Six);
-        self.invert_key(Seven);
-        self.invert_key(Eight);
-        self.invert_key(Nine);
+        for key in INVERTED_NUMBERS.iter() {
+            self.invert_key(*key);
+        }
         self
     }
 
     /// Converts a `Configuration` to a `Layout`. The `system` field of `Configuration` is used to
-    /// create macros with the correct keyboard shortcuts.
-    pub fn make(&mut self) -> Layout {
+    /// create macros with the correct keyboard shortcuts. Fails if a macro could not be built,
+    /// reporting the first such shortcut.
+    pub fn make(&mut self) -> Result<Layout, ConfigureError> {
         use self::Modifier::*;
 
+        if let Some((shortcut, error)) = self.macro_errors.iter().next() {
+            return Err(ConfigureError::Macro(shortcut.clone(), error.clone()));
+        }
+
         let remappings = if self.system.is_mac() {
             hashmap! {
-                KeyLayer::off(Key::Modifier(LeftControl)) => Some(KeyLayer::off(Key::Modifier(LeftWindowsCommand))),
-                KeyLayer::off(Key::Modifier(RightControl)) => Some(KeyLayer::off(Key::Modifier(RightWindowsCommand))),
-                KeyLayer::off(Key::Modifier(RightWindowsCommand)) => Some(KeyLayer::off(Key::Modifier(RightControl)))
+                KeyLayer::off(Key::Modifier(LeftControl)) => Some(Key::Modifier(LeftWindowsCommand).into()),
+                KeyLayer::off(Key::Modifier(RightControl)) => Some(Key::Modifier(RightWindowsCommand).into()),
+                KeyLayer::off(Key::Modifier(RightWindowsCommand)) => Some(Key::Modifier(RightControl).into())
             }
         } else {
             HashMap::new()
         };
 
-        Layout {
+        let mut macros: Vec<_> = self.macros.iter().collect();
+        macros.sort_unstable();
+
+        let macros = macros
+            .into_iter()
+            .map(|(k, v)| match v.to_macro_output(self.system) {
+                Ok(macro_output) => Ok((k.clone(), macro_output)),
+                Err(error) => Err(ConfigureError::Macro(k.clone(), error)),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Layout {
             remappings: self.with_remappings(remappings).remappings.clone(),
-            macros: self
-                .macros
-                .iter()
-                .map(|(k, v)| (k.clone(), v.to_macro_output(self.system)))
-                .collect(),
-        }
+            macros,
+        })
+    }
+
+    /// Makes the `Layout` and writes it into a slot of the v-drive mounted at `root`. See
+    /// `Layout::write_to_vdrive`. A `ConfigureError` is returned as an `InvalidData` error.
+    pub fn write_to_vdrive(&mut self, root: &Path, slot: LayoutSlot) -> io::Result<PathBuf> {
+        self.make()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?
+            .write_to_vdrive(root, slot)
     }
 }
@@ -0,0 +1,122 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::hash::Hash;
+
+use crate::keys::*;
+use crate::layout::{macro_line, remapping_line, Layout};
+use crate::macros::*;
+
+/// A difference in a single remapping or macro between an old and a new layout.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Hash, Debug)]
+pub enum Change<K, V> {
+    Added(K, V),
+    Removed(K, V),
+    /// Holds the old value followed by the new value.
+    Changed(K, V, V),
+}
+
+impl<K, V> Change<K, V> {
+    /// The remapped key or macro shortcut which changed.
+    pub fn key(&self) -> &K {
+        match self {
+            Change::Added(key, _) | Change::Removed(key, _) | Change::Changed(key, _, _) => key,
+        }
+    }
+}
+
+/// The remappings (including dead keys) and macros which differ between two `Layout`s, ordered
+/// as in a layout file.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct LayoutDiff {
+    pub remappings: Vec<Change<KeyLayer, Option<Target>>>,
+    pub macros: Vec<Change<Shortcut, MacroOutput>>,
+}
+
+impl LayoutDiff {
+    /// Computes the changes needed to go from the `old` layout to the `new` layout.
+    pub fn new(old: &Layout, new: &Layout) -> LayoutDiff {
+        LayoutDiff {
+            remappings: changes(&old.remappings, &new.remappings),
+            macros: changes(&old.macros, &new.macros),
+        }
+    }
+
+    /// Indicates whether the two layouts are identical.
+    pub fn is_empty(&self) -> bool {
+        self.remappings.is_empty() && self.macros.is_empty()
+    }
+
+    /// The changed remappings of keys in the given layer.
+    pub fn remappings_in(
+        &self,
+        keypad: Keypad,
+    ) -> impl Iterator<Item = &Change<KeyLayer, Option<Target>>> {
+        self.remappings
+            .iter()
+            .filter(move |change| change.key().keypad_state() == keypad)
+    }
+
+    /// The changed macros triggered by shortcuts in the given layer.
+    pub fn macros_in(
+        &self,
+        keypad: Keypad,
+    ) -> impl Iterator<Item = &Change<Shortcut, MacroOutput>> {
+        self.macros
+            .iter()
+            .filter(move |change| change.key().keypad == keypad)
+    }
+}
+
+impl fmt::Display for LayoutDiff {
+    /// Lists the changes per layer using the layout file format. Added lines are prefixed with
+    /// `+`, removed lines with `-` and changed lines with `~`, showing the old and new lines.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut lines: Vec<String> = Vec::new();
+
+        for (keypad, heading) in [(Keypad::Off, "top layer:"), (Keypad::On, "keypad layer:")] {
+            let mut layer_lines: Vec<String> = self
+                .remappings_in(keypad)
+                .map(|change| change_line(change, remapping_line))
+                .collect();
+
+            layer_lines.extend(
+                self.macros_in(keypad)
+                    .map(|change| change_line(change, macro_line)),
+            );
+
+            if !layer_lines.is_empty() {
+                lines.push(heading.to_string());
+                lines.extend(layer_lines);
+            }
+        }
+
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+fn change_line<K, V>(change: &Change<K, V>, line: impl Fn(&K, &V) -> String) -> String {
+    match change {
+        Change::Added(key, value) => format!("+ {}", line(key, value)),
+        Change::Removed(key, value) => format!("- {}", line(key, value)),
+        Change::Changed(key, old, new) => format!("~ {} => {}", line(key, old), line(key, new)),
+    }
+}
+
+fn changes<K, V>(old: &HashMap<K, V>, new: &HashMap<K, V>) -> Vec<Change<K, V>>
+where
+    K: Ord + Hash + Clone,
+    V: PartialEq + Clone,
+{
+    let keys: BTreeSet<&K> = old.keys().chain(new.keys()).collect();
+
+    keys.into_iter()
+        .filter_map(|key| match (old.get(key), new.get(key)) {
+            (None, Some(value)) => Some(Change::Added(key.clone(), value.clone())),
+            (Some(value), None) => Some(Change::Removed(key.clone(), value.clone())),
+            (Some(old), Some(new)) if old != new => {
+                Some(Change::Changed(key.clone(), old.clone(), new.clone()))
+            }
+            _ => None,
+        })
+        .collect()
+}
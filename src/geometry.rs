@@ -0,0 +1,132 @@
+//! A flat plan of the Advantage 2 as seen from above, for tools which measure finger travel or
+//! draw the keyboard. Distances are in key units, the 19 mm spacing of the keys in the wells, with
+//! `x` running right from the left edge of the left well and `y` running down from the top of the
+//! function row. The curve of the wells and the slant of the thumb clusters are ignored.
+
+use crate::info::*;
+use crate::keys::*;
+
+/// The size of a key unit in millimetres.
+pub const KEY_UNIT_MM: f64 = 19.0;
+
+/// Where the right half of the function row and the right well start.
+const RIGHT_HALF_X: f64 = 13.25;
+
+/// Where the left and right thumb clusters start.
+const LEFT_THUMB_X: f64 = 6.5;
+const RIGHT_THUMB_X: f64 = 10.0;
+const THUMB_Y: f64 = 4.25;
+
+/// The spacing, width and height of the low-profile function keys.
+const FUNCTION_KEY_PITCH: f64 = 0.7;
+const FUNCTION_KEY_WIDTH: f64 = 0.65;
+const FUNCTION_KEY_HEIGHT: f64 = 0.5;
+
+/// The width of the keys in the outer column of each well, e.g., `Tab`.
+const OUTER_KEY_WIDTH: f64 = 1.25;
+
+/// The thumb keys which are two units tall.
+const TALL_KEYS: &[NonModifier] = &[
+    NonModifier::Backspace,
+    NonModifier::Delete,
+    NonModifier::Enter,
+    NonModifier::Space,
+];
+
+/// The position and size of a key, in key units, and where it is on the keyboard.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Geometry {
+    pub hand: Hand,
+    pub cluster: Cluster,
+    /// The left edge of the key.
+    pub x: f64,
+    /// The top edge of the key.
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Geometry {
+    /// The position and size of `key`, or `None` if it is not on the keyboard, e.g., a pedal or
+    /// `lwin`. A keypad key has the geometry of the key it sits on.
+    pub fn of(key: &Key) -> Option<Geometry> {
+        let info = KeyInfo::of(key)?;
+        let hand = info.hand?;
+        let row = f64::from(info.row);
+        let column = f64::from(info.column);
+
+        let (x, y, width, height) = match (info.cluster, hand) {
+            (Cluster::FunctionRow, Hand::Left) => (
+                column * FUNCTION_KEY_PITCH,
+                0.0,
+                FUNCTION_KEY_WIDTH,
+                FUNCTION_KEY_HEIGHT,
+            ),
+            (Cluster::FunctionRow, Hand::Right) => (
+                RIGHT_HALF_X + column * FUNCTION_KEY_PITCH,
+                0.0,
+                FUNCTION_KEY_WIDTH,
+                FUNCTION_KEY_HEIGHT,
+            ),
+            (Cluster::Main, Hand::Left) if info.column == 0 => {
+                (0.0, row - 0.25, OUTER_KEY_WIDTH, 1.0)
+            }
+            (Cluster::Main, Hand::Left) => (column + 0.25, row - 0.25, 1.0, 1.0),
+            (Cluster::Main, Hand::Right) if info.column == 5 => {
+                (RIGHT_HALF_X + column, row - 0.25, OUTER_KEY_WIDTH, 1.0)
+            }
+            (Cluster::Main, Hand::Right) => (RIGHT_HALF_X + column, row - 0.25, 1.0, 1.0),
+            (Cluster::Thumb, _) => {
+                let thumb_x = match hand {
+                    Hand::Left => LEFT_THUMB_X,
+                    Hand::Right => RIGHT_THUMB_X,
+                };
+                let tall = match key {
+                    Key::NonModifier(key) => TALL_KEYS.contains(key),
+                    _ => false,
+                };
+
+                (
+                    thumb_x + column,
+                    THUMB_Y + row,
+                    1.0,
+                    if tall { 2.0 } else { 1.0 },
+                )
+            }
+            (Cluster::Pedal, _) => return None,
+        };
+
+        Some(Geometry {
+            hand,
+            cluster: info.cluster,
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+
+    /// The centre of the key.
+    pub fn centre(&self) -> (f64, f64) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+
+    /// The distance between the centres of two keys, in key units.
+    pub fn distance(&self, other: &Geometry) -> f64 {
+        let (x1, y1) = self.centre();
+        let (x2, y2) = other.centre();
+
+        (x2 - x1).hypot(y2 - y1)
+    }
+
+    /// Indicates whether the point is on the key.
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+/// The key at a point, if any, e.g., for mapping the keys of another keyboard onto the
+/// Advantage 2.
+pub fn key_at(x: f64, y: f64) -> Option<Key> {
+    Key::all().find(|key| Geometry::of(key).is_some_and(|g| g.contains(x, y)))
+}
@@ -0,0 +1,225 @@
+//! Where the keys of the Advantage 2 are and which finger presses them, for tools which analyse
+//! or draw layouts.
+
+use crate::keys::*;
+
+/// The hand used to press a key.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Debug)]
+pub enum Hand {
+    Left,
+    Right,
+}
+
+/// The finger used to press a key when touch typing.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Debug)]
+pub enum Finger {
+    Pinky,
+    Ring,
+    Middle,
+    Index,
+    Thumb,
+}
+
+/// The group of keys a key belongs to.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Debug)]
+pub enum Cluster {
+    /// The row of low-profile keys above the key wells.
+    FunctionRow,
+    /// The key wells.
+    Main,
+    /// The thumb clusters.
+    Thumb,
+    /// The optional foot pedals.
+    Pedal,
+}
+
+/// Describes a physical key. Rows and columns count from zero within the key's cluster and half
+/// of the keyboard, with rows running from the top and columns from the left. The function row is
+/// row 0 of the key wells' numbering, so the number row is row 1.
+#[derive(PartialEq, Eq, Clone, Copy, Hash, Debug)]
+pub struct KeyInfo {
+    /// The hand pressing the key, or `None` for a pedal.
+    pub hand: Option<Hand>,
+    /// The finger pressing the key, or `None` for a pedal.
+    pub finger: Option<Finger>,
+    pub cluster: Cluster,
+    pub row: u8,
+    pub column: u8,
+    /// The legend printed on the key.
+    pub legend: &'static str,
+}
+
+/// A key's hand, cluster, row, column and legend.
+type Position = (Option<Hand>, Cluster, u8, u8, &'static str);
+
+const fn position(
+    hand: Option<Hand>,
+    cluster: Cluster,
+    row: u8,
+    column: u8,
+    legend: &'static str,
+) -> Position {
+    (hand, cluster, row, column, legend)
+}
+
+const LEFT: Option<Hand> = Some(Hand::Left);
+const RIGHT: Option<Hand> = Some(Hand::Right);
+
+/// The position and legend of every physical key. The Advantage 2 has no left Windows key, no
+/// right Alt key and no Insert key in the top layer.
+const KEY_POSITIONS: &[(Key, Position)] = {
+    use self::Cluster::*;
+    use self::Modifier::*;
+    use self::NonModifier::*;
+
+    const fn m(key: Modifier) -> Key {
+        Key::Modifier(key)
+    }
+
+    const fn n(key: NonModifier) -> Key {
+        Key::NonModifier(key)
+    }
+
+    &[
+        (n(Escape), position(LEFT, FunctionRow, 0, 0, "Esc")),
+        (n(F1), position(LEFT, FunctionRow, 0, 1, "F1")),
+        (n(F2), position(LEFT, FunctionRow, 0, 2, "F2")),
+        (n(F3), position(LEFT, FunctionRow, 0, 3, "F3")),
+        (n(F4), position(LEFT, FunctionRow, 0, 4, "F4")),
+        (n(F5), position(LEFT, FunctionRow, 0, 5, "F5")),
+        (n(F6), position(LEFT, FunctionRow, 0, 6, "F6")),
+        (n(F7), position(LEFT, FunctionRow, 0, 7, "F7")),
+        (n(F8), position(LEFT, FunctionRow, 0, 8, "F8")),
+        (n(F9), position(RIGHT, FunctionRow, 0, 0, "F9")),
+        (n(F10), position(RIGHT, FunctionRow, 0, 1, "F10")),
+        (n(F11), position(RIGHT, FunctionRow, 0, 2, "F11")),
+        (n(F12), position(RIGHT, FunctionRow, 0, 3, "F12")),
+        (
+            n(PrintScreen),
+            position(RIGHT, FunctionRow, 0, 4, "Print Screen"),
+        ),
+        (
+            n(ScrollLock),
+            position(RIGHT, FunctionRow, 0, 5, "Scroll Lock"),
+        ),
+        (n(Pause), position(RIGHT, FunctionRow, 0, 6, "Pause")),
+        (n(Equals), position(LEFT, Main, 1, 0, "=")),
+        (n(One), position(LEFT, Main, 1, 1, "1")),
+        (n(Two), position(LEFT, Main, 1, 2, "2")),
+        (n(Three), position(LEFT, Main, 1, 3, "3")),
+        (n(Four), position(LEFT, Main, 1, 4, "4")),
+        (n(Five), position(LEFT, Main, 1, 5, "5")),
+        (n(Tab), position(LEFT, Main, 2, 0, "Tab")),
+        (n(Q), position(LEFT, Main, 2, 1, "Q")),
+        (n(W), position(LEFT, Main, 2, 2, "W")),
+        (n(E), position(LEFT, Main, 2, 3, "E")),
+        (n(R), position(LEFT, Main, 2, 4, "R")),
+        (n(T), position(LEFT, Main, 2, 5, "T")),
+        (n(CapsLock), position(LEFT, Main, 3, 0, "Caps Lock")),
+        (n(A), position(LEFT, Main, 3, 1, "A")),
+        (n(S), position(LEFT, Main, 3, 2, "S")),
+        (n(D), position(LEFT, Main, 3, 3, "D")),
+        (n(F), position(LEFT, Main, 3, 4, "F")),
+        (n(G), position(LEFT, Main, 3, 5, "G")),
+        (m(LeftShift), position(LEFT, Main, 4, 0, "Shift")),
+        (n(Z), position(LEFT, Main, 4, 1, "Z")),
+        (n(X), position(LEFT, Main, 4, 2, "X")),
+        (n(C), position(LEFT, Main, 4, 3, "C")),
+        (n(V), position(LEFT, Main, 4, 4, "V")),
+        (n(B), position(LEFT, Main, 4, 5, "B")),
+        (n(Backtick), position(LEFT, Main, 5, 1, "`")),
+        (n(International), position(LEFT, Main, 5, 2, "Intl")),
+        (n(LeftArrow), position(LEFT, Main, 5, 3, "Left")),
+        (n(RightArrow), position(LEFT, Main, 5, 4, "Right")),
+        (n(Six), position(RIGHT, Main, 1, 0, "6")),
+        (n(Seven), position(RIGHT, Main, 1, 1, "7")),
+        (n(Eight), position(RIGHT, Main, 1, 2, "8")),
+        (n(Nine), position(RIGHT, Main, 1, 3, "9")),
+        (n(Zero), position(RIGHT, Main, 1, 4, "0")),
+        (n(Hyphen), position(RIGHT, Main, 1, 5, "-")),
+        (n(Y), position(RIGHT, Main, 2, 0, "Y")),
+        (n(U), position(RIGHT, Main, 2, 1, "U")),
+        (n(I), position(RIGHT, Main, 2, 2, "I")),
+        (n(O), position(RIGHT, Main, 2, 3, "O")),
+        (n(P), position(RIGHT, Main, 2, 4, "P")),
+        (n(BackSlash), position(RIGHT, Main, 2, 5, "\\")),
+        (n(H), position(RIGHT, Main, 3, 0, "H")),
+        (n(J), position(RIGHT, Main, 3, 1, "J")),
+        (n(K), position(RIGHT, Main, 3, 2, "K")),
+        (n(L), position(RIGHT, Main, 3, 3, "L")),
+        (n(SemiColon), position(RIGHT, Main, 3, 4, ";")),
+        (n(Quote), position(RIGHT, Main, 3, 5, "'")),
+        (n(N), position(RIGHT, Main, 4, 0, "N")),
+        (n(M), position(RIGHT, Main, 4, 1, "M")),
+        (n(Comma), position(RIGHT, Main, 4, 2, ",")),
+        (n(FullStop), position(RIGHT, Main, 4, 3, ".")),
+        (n(ForwardSlash), position(RIGHT, Main, 4, 4, "/")),
+        (m(RightShift), position(RIGHT, Main, 4, 5, "Shift")),
+        (n(UpArrow), position(RIGHT, Main, 5, 1, "Up")),
+        (n(DownArrow), position(RIGHT, Main, 5, 2, "Down")),
+        (n(OpenBracket), position(RIGHT, Main, 5, 3, "[")),
+        (n(CloseBracket), position(RIGHT, Main, 5, 4, "]")),
+        (m(LeftControl), position(LEFT, Thumb, 0, 1, "Ctrl")),
+        (m(LeftAlt), position(LEFT, Thumb, 0, 2, "Alt")),
+        (n(Backspace), position(LEFT, Thumb, 1, 0, "Backspace")),
+        (n(Delete), position(LEFT, Thumb, 1, 1, "Delete")),
+        (n(Home), position(LEFT, Thumb, 1, 2, "Home")),
+        (n(End), position(LEFT, Thumb, 2, 2, "End")),
+        (m(RightWindowsCommand), position(RIGHT, Thumb, 0, 0, "Win")),
+        (m(RightControl), position(RIGHT, Thumb, 0, 1, "Ctrl")),
+        (n(PageUp), position(RIGHT, Thumb, 1, 0, "Page Up")),
+        (n(Enter), position(RIGHT, Thumb, 1, 1, "Enter")),
+        (n(Space), position(RIGHT, Thumb, 1, 2, "Space")),
+        (n(PageDown), position(RIGHT, Thumb, 2, 0, "Page Down")),
+        (n(LeftPedal), position(None, Pedal, 0, 0, "Tab")),
+        (n(MiddlePedal), position(None, Pedal, 0, 1, "Keypad Shift")),
+        (n(RightPedal), position(None, Pedal, 0, 2, "Keypad Enter")),
+    ]
+};
+
+impl KeyInfo {
+    /// Describes `key`, or returns `None` if it is not a physical key of the Advantage 2, e.g.,
+    /// `lwin`. A keypad key is described by the key it sits on.
+    pub fn of(key: &Key) -> Option<KeyInfo> {
+        let key = match key {
+            Key::Keypad(keypad_key) => Key::NonModifier(keypad_key.physical_key()),
+            key => key.clone(),
+        };
+
+        KEY_POSITIONS.iter().find(|(k, _)| *k == key).map(
+            |&(_, (hand, cluster, row, column, legend))| KeyInfo {
+                hand,
+                finger: finger(hand, cluster, column),
+                cluster,
+                row,
+                column,
+                legend,
+            },
+        )
+    }
+}
+
+/// The finger for a key. A function key beyond the key wells' six columns is pressed by the finger
+/// of the nearest column.
+fn finger(hand: Option<Hand>, cluster: Cluster, column: u8) -> Option<Finger> {
+    use self::Finger::*;
+
+    let hand = hand?;
+
+    if cluster == Cluster::Thumb {
+        return Some(Thumb);
+    }
+
+    // Counted from the inner edge of the hand, so the two halves mirror each other.
+    let from_inner = match hand {
+        Hand::Left => 5u8.saturating_sub(column),
+        Hand::Right => column.min(5),
+    };
+
+    Some(match from_inner {
+        0 | 1 => Index,
+        2 => Middle,
+        3 => Ring,
+        _ => Pinky,
+    })
+}
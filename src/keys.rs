@@ -1,8 +1,18 @@
 use std::collections::BTreeSet;
 use std::fmt;
+use std::str::FromStr;
+
+use crate::geometry::Geometry;
+use crate::info::KeyInfo;
+use crate::parse::{split_tokens, ParseErrorKind};
 
 /// Models whether the keypad layer is on or off.
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 pub enum Keypad {
     Off,
     On,
@@ -21,26 +31,54 @@ pub enum Modifier {
     RightAlt,
 }
 
+/// Firmware tokens for the modifier keys. This table drives both `Display` and `FromStr` so the
+/// two can never disagree.
+const MODIFIER_TOKENS: &[(Modifier, &str)] = {
+    use self::Modifier::*;
+
+    &[
+        (LeftShift, "lshift"),
+        (RightShift, "rshift"),
+        (LeftWindowsCommand, "lwin"),
+        (RightWindowsCommand, "rwin"),
+        (LeftControl, "lctrl"),
+        (RightControl, "rctrl"),
+        (LeftAlt, "lalt"),
+        (RightAlt, "ralt"),
+    ]
+};
+
+impl Modifier {
+    /// The firmware token for the modifier, e.g., `lshift`.
+    pub fn token(self) -> &'static str {
+        lookup_token(MODIFIER_TOKENS, self)
+    }
+
+    /// Every modifier key, in order.
+    pub fn all() -> impl Iterator<Item = Modifier> {
+        MODIFIER_TOKENS.iter().map(|(key, _)| *key)
+    }
+}
+
 impl fmt::Display for Modifier {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        use self::Modifier::*;
+        write!(f, "{}", self.token())
+    }
+}
 
-        match self {
-            LeftShift => write!(f, "lshift"),
-            RightShift => write!(f, "rshift"),
-            LeftWindowsCommand => write!(f, "lwin"),
-            RightWindowsCommand => write!(f, "rwin"),
-            LeftControl => write!(f, "lctrl"),
-            RightControl => write!(f, "rctrl"),
-            LeftAlt => write!(f, "lalt"),
-            RightAlt => write!(f, "ralt"),
-        }
+impl FromStr for Modifier {
+    type Err = ParseErrorKind;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        lookup_key(MODIFIER_TOKENS, s).ok_or_else(|| ParseErrorKind::UnknownToken(s.to_string()))
     }
 }
 
-/// Represents the non-modifier keys.
+/// Represents the non-modifier keys, including the inputs of foot pedals plugged into the
+/// keyboard's pedal jack.
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Debug)]
 pub enum NonModifier {
+    Escape,
     F1,
     F2,
     F3,
@@ -53,6 +91,9 @@ pub enum NonModifier {
     F10,
     F11,
     F12,
+    PrintScreen,
+    ScrollLock,
+    Pause,
     One,
     Two,
     Three,
@@ -116,60 +157,368 @@ pub enum NonModifier {
     End,
     CapsLock,
     International,
+    LeftPedal,
+    MiddlePedal,
+    RightPedal,
+}
+
+/// Firmware tokens for the non-modifier keys. Letters and function keys are written in upper
+/// case in remappings and in lower case in macros; tokens are matched case-insensitively. A
+/// pedal's token names what it sends from the factory, e.g., `lp-tab` for the left pedal.
+const NON_MODIFIER_TOKENS: &[(NonModifier, &str)] = {
+    use self::NonModifier::*;
+
+    &[
+        (Escape, "escape"),
+        (F1, "F1"),
+        (F2, "F2"),
+        (F3, "F3"),
+        (F4, "F4"),
+        (F5, "F5"),
+        (F6, "F6"),
+        (F7, "F7"),
+        (F8, "F8"),
+        (F9, "F9"),
+        (F10, "F10"),
+        (F11, "F11"),
+        (F12, "F12"),
+        (PrintScreen, "prtscr"),
+        (ScrollLock, "scroll"),
+        (Pause, "pause"),
+        (One, "1"),
+        (Two, "2"),
+        (Three, "3"),
+        (Four, "4"),
+        (Five, "5"),
+        (Six, "6"),
+        (Seven, "7"),
+        (Eight, "8"),
+        (Nine, "9"),
+        (Zero, "0"),
+        (Backtick, "`"),
+        (Hyphen, "hyphen"),
+        (Equals, "="),
+        (A, "A"),
+        (B, "B"),
+        (C, "C"),
+        (D, "D"),
+        (E, "E"),
+        (F, "F"),
+        (G, "G"),
+        (H, "H"),
+        (I, "I"),
+        (J, "J"),
+        (K, "K"),
+        (L, "L"),
+        (M, "M"),
+        (N, "N"),
+        (O, "O"),
+        (P, "P"),
+        (Q, "Q"),
+        (R, "R"),
+        (S, "S"),
+        (T, "T"),
+        (U, "U"),
+        (V, "V"),
+        (W, "W"),
+        (X, "X"),
+        (Y, "Y"),
+        (Z, "Z"),
+        (BackSlash, "\\"),
+        (SemiColon, ";"),
+        (Quote, "'"),
+        (Comma, ","),
+        (FullStop, "."),
+        (ForwardSlash, "/"),
+        (OpenBracket, "obrack"),
+        (CloseBracket, "cbrack"),
+        (Enter, "enter"),
+        (PageUp, "pup"),
+        (Tab, "tab"),
+        (PageDown, "pdown"),
+        (Space, "space"),
+        (LeftArrow, "left"),
+        (Delete, "delete"),
+        (RightArrow, "right"),
+        (Backspace, "bspace"),
+        (UpArrow, "up"),
+        (Insert, "insert"),
+        (DownArrow, "down"),
+        (Home, "home"),
+        (End, "end"),
+        (CapsLock, "caps"),
+        (International, r#"intl-\"#),
+        (LeftPedal, "lp-tab"),
+        (MiddlePedal, "mp-kpshf"),
+        (RightPedal, "rp-kpent"),
+    ]
+};
+
+impl NonModifier {
+    /// The firmware token for the key, e.g., `obrack`.
+    pub fn token(self) -> &'static str {
+        lookup_token(NON_MODIFIER_TOKENS, self)
+    }
+
+    /// Every non-modifier key, in order.
+    pub fn all() -> impl Iterator<Item = NonModifier> {
+        NON_MODIFIER_TOKENS.iter().map(|(key, _)| *key)
+    }
 }
 
 impl fmt::Display for NonModifier {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        use crate::keys::NonModifier::*;
+        write!(f, "{}", self.token())
+    }
+}
 
-        match self {
-            One => write!(f, "1"),
-            Two => write!(f, "2"),
-            Three => write!(f, "3"),
-            Four => write!(f, "4"),
-            Five => write!(f, "5"),
-            Six => write!(f, "6"),
-            Seven => write!(f, "7"),
-            Eight => write!(f, "8"),
-            Nine => write!(f, "9"),
-            Zero => write!(f, "0"),
-            Backtick => write!(f, "`"),
-            Hyphen => write!(f, "hyphen"),
-            Equals => write!(f, "="),
-            BackSlash => write!(f, "\\"),
-            SemiColon => write!(f, ";"),
-            Quote => write!(f, "\'"),
-            Comma => write!(f, ","),
-            FullStop => write!(f, "."),
-            ForwardSlash => write!(f, "/"),
-            OpenBracket => write!(f, "obrack"),
-            CloseBracket => write!(f, "cbrack"),
-            Enter => write!(f, "enter"),
-            PageUp => write!(f, "pup"),
-            Tab => write!(f, "tab"),
-            PageDown => write!(f, "pdown"),
-            Space => write!(f, "space"),
-            LeftArrow => write!(f, "left"),
-            Delete => write!(f, "delete"),
-            RightArrow => write!(f, "right"),
-            Backspace => write!(f, "bspace"),
-            UpArrow => write!(f, "up"),
-            Insert => write!(f, "insert"),
-            DownArrow => write!(f, "down"),
-            Home => write!(f, "home"),
-            End => write!(f, "end"),
-            CapsLock => write!(f, "caps"),
-            International => write!(f, r#"intl-\"#),
-            key => write!(f, "{}", format!("{:?}", key)),
-        }
+impl FromStr for NonModifier {
+    type Err = ParseErrorKind;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        lookup_key(NON_MODIFIER_TOKENS, s)
+            .ok_or_else(|| ParseErrorKind::UnknownToken(s.to_string()))
     }
 }
 
+/// The keys of the keypad layer which have a dedicated token, e.g., `kp1` or `kpplus`, rather
+/// than `kp-` followed by the token of the physical key they sit on.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Debug)]
+pub enum KeypadKey {
+    Kp0,
+    Kp1,
+    Kp2,
+    Kp3,
+    Kp4,
+    Kp5,
+    Kp6,
+    Kp7,
+    Kp8,
+    Kp9,
+    NumLock,
+    Decimal,
+    Equals,
+    Divide,
+    Plus,
+    Multiply,
+    Minus,
+    Enter,
+    Insert,
+}
+
+/// Firmware tokens for the keypad keys.
+const KEYPAD_KEY_TOKENS: &[(KeypadKey, &str)] = {
+    use self::KeypadKey::*;
+
+    &[
+        (Kp0, "kp0"),
+        (Kp1, "kp1"),
+        (Kp2, "kp2"),
+        (Kp3, "kp3"),
+        (Kp4, "kp4"),
+        (Kp5, "kp5"),
+        (Kp6, "kp6"),
+        (Kp7, "kp7"),
+        (Kp8, "kp8"),
+        (Kp9, "kp9"),
+        (NumLock, "numlk"),
+        (Decimal, "k."),
+        (Equals, "k="),
+        (Divide, "kpdiv"),
+        (Plus, "kpplus"),
+        (Multiply, "kpmult"),
+        (Minus, "kpmin"),
+        (Enter, "kpenter1"),
+        (Insert, "kp-insert"),
+    ]
+};
+
+/// The physical key each keypad key sits on. `kp-insert` sits on the international key.
+const KEYPAD_KEY_POSITIONS: &[(KeypadKey, NonModifier)] = {
+    use self::KeypadKey::*;
+
+    &[
+        (Kp0, NonModifier::Space),
+        (Kp1, NonModifier::M),
+        (Kp2, NonModifier::Comma),
+        (Kp3, NonModifier::FullStop),
+        (Kp4, NonModifier::J),
+        (Kp5, NonModifier::K),
+        (Kp6, NonModifier::L),
+        (Kp7, NonModifier::U),
+        (Kp8, NonModifier::I),
+        (Kp9, NonModifier::O),
+        (NumLock, NonModifier::Seven),
+        (Decimal, NonModifier::CloseBracket),
+        (Equals, NonModifier::Eight),
+        (Divide, NonModifier::Nine),
+        (Plus, NonModifier::SemiColon),
+        (Multiply, NonModifier::Zero),
+        (Minus, NonModifier::P),
+        (Enter, NonModifier::ForwardSlash),
+        (Insert, NonModifier::International),
+    ]
+};
+
+impl KeypadKey {
+    /// The firmware token for the key, e.g., `kpplus`.
+    pub fn token(self) -> &'static str {
+        lookup_token(KEYPAD_KEY_TOKENS, self)
+    }
+
+    /// Every keypad key, in order.
+    pub fn all() -> impl Iterator<Item = KeypadKey> {
+        KEYPAD_KEY_TOKENS.iter().map(|(key, _)| *key)
+    }
+
+    /// The physical key the keypad key sits on, e.g., `M` for `Kp1`.
+    pub fn physical_key(self) -> NonModifier {
+        KEYPAD_KEY_POSITIONS
+            .iter()
+            .find(|(k, _)| *k == self)
+            .map(|(_, key)| *key)
+            .expect("every keypad key has a position")
+    }
+
+    /// The keypad key sitting on a physical key, if any.
+    pub fn from_physical_key(key: NonModifier) -> Option<KeypadKey> {
+        KEYPAD_KEY_POSITIONS
+            .iter()
+            .find(|(_, k)| *k == key)
+            .map(|(keypad_key, _)| *keypad_key)
+    }
+}
+
+impl fmt::Display for KeypadKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.token())
+    }
+}
+
+impl FromStr for KeypadKey {
+    type Err = ParseErrorKind;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        lookup_key(KEYPAD_KEY_TOKENS, s).ok_or_else(|| ParseErrorKind::UnknownToken(s.to_string()))
+    }
+}
+
+/// Represents the actions the keyboard can send which are not keys of a standard keyboard, i.e.,
+/// multimedia keys, mouse clicks and switching to the keypad layer. Actions may be the `Target` of
+/// a remapping or part of a macro, but are not keys, so they cannot be remapped themselves.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Debug)]
+pub enum Action {
+    Mute,
+    VolumeDown,
+    VolumeUp,
+    PlayPause,
+    NextTrack,
+    PreviousTrack,
+    Calculator,
+    LeftClick,
+    MiddleClick,
+    RightClick,
+    /// Switches to the keypad layer while held.
+    KeypadShift,
+    /// Switches between the top and keypad layers.
+    KeypadToggle,
+}
+
+/// Firmware tokens for the actions.
+const ACTION_TOKENS: &[(Action, &str)] = {
+    use self::Action::*;
+
+    &[
+        (Mute, "mute"),
+        (VolumeDown, "vol-"),
+        (VolumeUp, "vol+"),
+        (PlayPause, "play"),
+        (NextTrack, "next"),
+        (PreviousTrack, "prev"),
+        (Calculator, "calc"),
+        (LeftClick, "lmouse"),
+        (MiddleClick, "mmouse"),
+        (RightClick, "rmouse"),
+        (KeypadShift, "kpshft"),
+        (KeypadToggle, "kptoggle"),
+    ]
+};
+
+impl Action {
+    /// The firmware token for the action, e.g., `vol+`.
+    pub fn token(self) -> &'static str {
+        lookup_token(ACTION_TOKENS, self)
+    }
+
+    /// Every action, in order.
+    pub fn all() -> impl Iterator<Item = Action> {
+        ACTION_TOKENS.iter().map(|(action, _)| *action)
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.token())
+    }
+}
+
+impl FromStr for Action {
+    type Err = ParseErrorKind;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        lookup_key(ACTION_TOKENS, s).ok_or_else(|| ParseErrorKind::UnknownToken(s.to_string()))
+    }
+}
+
+fn lookup_token<K: PartialEq + Copy>(table: &[(K, &'static str)], key: K) -> &'static str {
+    table
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, token)| *token)
+        .expect("every key has a token")
+}
+
+fn lookup_key<K: Copy>(table: &[(K, &str)], token: &str) -> Option<K> {
+    table
+        .iter()
+        .find(|(_, t)| t.eq_ignore_ascii_case(token))
+        .map(|(key, _)| *key)
+}
+
 /// Represents the keys of a Kinesis Advantage 2. A key is either a modifier or non-modifier key.
+/// A keypad key names a non-modifier key in the keypad layer.
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Hash, Debug)]
 pub enum Key {
     Modifier(Modifier),
     NonModifier(NonModifier),
+    Keypad(KeypadKey),
+}
+
+impl From<KeypadKey> for Key {
+    fn from(key: KeypadKey) -> Self {
+        Key::Keypad(key)
+    }
+}
+
+impl Key {
+    /// Every modifier and non-modifier key, in order. Keypad keys are not included as they are
+    /// not separate physical keys.
+    pub fn all() -> impl Iterator<Item = Key> {
+        Modifier::all()
+            .map(Key::Modifier)
+            .chain(NonModifier::all().map(Key::NonModifier))
+    }
+
+    /// The position, hand, finger and legend of the key, if it is a physical key of the
+    /// Advantage 2. A keypad key is described by the key it sits on.
+    pub fn info(&self) -> Option<KeyInfo> {
+        KeyInfo::of(self)
+    }
+
+    /// The position and size of the key on the keyboard, if it is on it.
+    pub fn geometry(&self) -> Option<Geometry> {
+        Geometry::of(self)
+    }
 }
 
 impl fmt::Display for Key {
@@ -177,11 +526,25 @@ impl fmt::Display for Key {
         match self {
             Key::Modifier(key) => write!(f, "{}", key),
             Key::NonModifier(key) => write!(f, "{}", key),
+            Key::Keypad(key) => write!(f, "{}", key),
         }
     }
 }
 
-/// Models a `Key` together with information on whether the keypad layer is on or off.
+impl FromStr for Key {
+    type Err = ParseErrorKind;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse()
+            .map(Key::Modifier)
+            .or_else(|_| s.parse().map(Key::NonModifier))
+            .or_else(|_| s.parse().map(Key::Keypad))
+    }
+}
+
+/// Models a `Key` together with information on whether the keypad layer is on or off. A
+/// non-modifier key in the keypad layer is held as the `KeypadKey` sitting on it, if any, so that
+/// each key in a layer has a single representation.
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Hash, Debug)]
 pub struct KeyLayer {
     keypad_state: Keypad,
@@ -189,11 +552,40 @@ pub struct KeyLayer {
 }
 
 impl KeyLayer {
-    /// A convenience method for creating a `KeyLayer`.
+    /// A convenience method for creating a `KeyLayer`. The keypad state is normalized so that
+    /// each key has one representation: a keypad key is always in the keypad layer, whatever
+    /// `keypad_state` is, and a non-modifier key in the keypad layer becomes the keypad key
+    /// sitting on it, if any. `insert` in the keypad layer becomes `kp-insert`, the keypad key,
+    /// as the two share a token.
     pub fn new(keypad_state: Keypad, key: Key) -> Self {
+        let (keypad_state, key) = match (keypad_state, key) {
+            (_, Key::Keypad(key)) => (Keypad::On, Key::Keypad(key)),
+            (Keypad::On, Key::NonModifier(NonModifier::Insert)) => {
+                (Keypad::On, Key::Keypad(KeypadKey::Insert))
+            }
+            (Keypad::On, Key::NonModifier(key)) => match KeypadKey::from_physical_key(key) {
+                Some(keypad_key) => (Keypad::On, Key::Keypad(keypad_key)),
+                None => (Keypad::On, Key::NonModifier(key)),
+            },
+            (keypad_state, key) => (keypad_state, key),
+        };
+
         KeyLayer { keypad_state, key }
     }
 
+    /// Every key in the top layer followed by every one in the keypad layer, where a key with a
+    /// keypad key sitting on it is given as the keypad key. `insert` is left out of the keypad
+    /// layer as it is the same as `kp-insert`, which is given for `international`.
+    pub fn all() -> impl Iterator<Item = KeyLayer> {
+        [Keypad::Off, Keypad::On]
+            .iter()
+            .flat_map(|&keypad| Key::all().map(move |key| (keypad, key)))
+            .filter(|(keypad, key)| {
+                *keypad == Keypad::Off || *key != Key::NonModifier(NonModifier::Insert)
+            })
+            .map(|(keypad, key)| KeyLayer::new(keypad, key))
+    }
+
     /// Creates a `KeyLayer` value with the keypad layer set to off.
     pub fn off(key: Key) -> Self {
         KeyLayer::new(Keypad::Off, key)
@@ -203,43 +595,131 @@ impl KeyLayer {
     pub fn on(key: Key) -> Self {
         KeyLayer::new(Keypad::On, key)
     }
+
+    /// Indicates whether the key is in the keypad layer.
+    pub fn keypad_state(&self) -> Keypad {
+        self.keypad_state
+    }
+
+    /// The key, regardless of layer.
+    pub fn key(&self) -> &Key {
+        &self.key
+    }
+
+    /// The non-modifier key physically pressed, if the key is one, e.g., `M` for `kp1`.
+    pub fn non_modifier(&self) -> Option<NonModifier> {
+        match self.key {
+            Key::NonModifier(key) => Some(key),
+            Key::Keypad(key) => Some(key.physical_key()),
+            _ => None,
+        }
+    }
+
+    /// What the key sends when it is not remapped. A keypad key sends itself, e.g., `kp1` for `M`
+    /// in the keypad layer, while any other key in the keypad layer sends the same as in the top
+    /// layer, e.g., `A` for `kp-A`.
+    pub fn factory_output(&self) -> KeyLayer {
+        match self.key {
+            Key::Modifier(_) | Key::NonModifier(_) => KeyLayer::off(self.key.clone()),
+            Key::Keypad(_) => self.clone(),
+        }
+    }
 }
 
 impl fmt::Display for KeyLayer {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if self.keypad_state == Keypad::Off {
-            write!(f, "{}", self.key)
-        } else {
-            match self.key {
-                Key::NonModifier(NonModifier::Space) => write!(f, "kp0"),
-                Key::NonModifier(NonModifier::M) => write!(f, "kp1"),
-                Key::NonModifier(NonModifier::Comma) => write!(f, "kp2"),
-                Key::NonModifier(NonModifier::FullStop) => write!(f, "kp3"),
-                Key::NonModifier(NonModifier::J) => write!(f, "kp4"),
-                Key::NonModifier(NonModifier::K) => write!(f, "kp5"),
-                Key::NonModifier(NonModifier::L) => write!(f, "kp6"),
-                Key::NonModifier(NonModifier::U) => write!(f, "kp7"),
-                Key::NonModifier(NonModifier::I) => write!(f, "kp8"),
-                Key::NonModifier(NonModifier::O) => write!(f, "kp9"),
-                Key::NonModifier(NonModifier::Seven) => write!(f, "numlk"),
-                Key::NonModifier(NonModifier::CloseBracket) => write!(f, "k."),
-                Key::NonModifier(NonModifier::Eight) => write!(f, "k="),
-                Key::NonModifier(NonModifier::Nine) => write!(f, "kpdiv"),
-                Key::NonModifier(NonModifier::SemiColon) => write!(f, "kpplus"),
-                Key::NonModifier(NonModifier::Zero) => write!(f, "kpmult"),
-                Key::NonModifier(NonModifier::P) => write!(f, "kpmin"),
-                Key::NonModifier(NonModifier::ForwardSlash) => write!(f, "kpenter1"),
-                Key::NonModifier(NonModifier::International) => write!(f, "kp-insert"),
-                Key::NonModifier(ref key) => write!(f, "kp-{}", key),
-                Key::Modifier(ref key) => write!(f, "kp-{}", key),
-            }
+        match (self.keypad_state, &self.key) {
+            (Keypad::Off, key) | (Keypad::On, key @ Key::Keypad(_)) => write!(f, "{}", key),
+            (Keypad::On, key) => write!(f, "kp-{}", key),
         }
     }
 }
 
+impl FromStr for KeyLayer {
+    type Err = ParseErrorKind;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(key) = s.parse::<KeypadKey>() {
+            return Ok(KeyLayer::on(Key::Keypad(key)));
+        }
+
+        match s.get(..3) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("kp-") => s[3..].parse().map(KeyLayer::on),
+            _ => s.parse().map(KeyLayer::off),
+        }
+        .map_err(|_| ParseErrorKind::UnknownToken(s.to_string()))
+    }
+}
+
+/// What a key is remapped to: a key, in either layer, or an action. Only a `Key` can be remapped,
+/// so an action is never the key of a remapping.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Hash, Debug)]
+pub enum Target {
+    Key(KeyLayer),
+    Action(Action),
+}
+
+impl Target {
+    /// The target with a key moved to the given layer, e.g., `kp0` for `space` in the keypad
+    /// layer. An action is the same in both layers.
+    pub fn in_layer(self, keypad_state: Keypad) -> Target {
+        match self {
+            Target::Key(key) => Target::Key(KeyLayer::new(keypad_state, key.key)),
+            action => action,
+        }
+    }
+
+    /// What the target sends, see `KeyLayer::factory_output`.
+    pub fn factory_output(&self) -> Target {
+        match self {
+            Target::Key(key) => Target::Key(key.factory_output()),
+            action => action.clone(),
+        }
+    }
+}
+
+/// A key in the top layer.
+impl From<Key> for Target {
+    fn from(key: Key) -> Self {
+        Target::Key(KeyLayer::off(key))
+    }
+}
+
+impl From<KeyLayer> for Target {
+    fn from(key: KeyLayer) -> Self {
+        Target::Key(key)
+    }
+}
+
+impl From<Action> for Target {
+    fn from(action: Action) -> Self {
+        Target::Action(action)
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Target::Key(key) => write!(f, "{}", key),
+            Target::Action(action) => write!(f, "{}", action),
+        }
+    }
+}
+
+impl FromStr for Target {
+    type Err = ParseErrorKind;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse()
+            .map(Target::Key)
+            .or_else(|_| s.parse().map(Target::Action))
+    }
+}
+
 /// A datatype to model using a non-modifier key. A non-modifier key may be used with or without
 /// a shift modifier key.
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct KeyPress {
     pub shifted: bool,
     pub key: NonModifier,
@@ -290,6 +770,11 @@ impl Shortcut {
     pub fn keypad_on(modifiers: BTreeSet<Modifier>, non_modifier: NonModifier) -> Shortcut {
         Shortcut::new(Keypad::On, modifiers, non_modifier)
     }
+
+    /// Creates a shortcut in the keypad layer using a keypad key, e.g., `{kp-lctrl}{kp1}`.
+    pub fn keypad_key(modifiers: BTreeSet<Modifier>, key: KeypadKey) -> Shortcut {
+        Shortcut::keypad_on(modifiers, key.physical_key())
+    }
 }
 
 impl fmt::Display for Shortcut {
@@ -313,3 +798,34 @@ impl fmt::Display for Shortcut {
         write!(f, "{}", string)
     }
 }
+
+impl FromStr for Shortcut {
+    type Err = ParseErrorKind;
+
+    /// Parses a shortcut written as in a macro trigger, e.g., `{rshift}{lalt}{t}`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid_shortcut = || ParseErrorKind::InvalidShortcut(s.to_string());
+
+        let tokens = split_tokens(s)?;
+        let (last, modifier_tokens) = tokens.split_last().ok_or_else(invalid_shortcut)?;
+        let last: KeyLayer = last.parse()?;
+
+        let non_modifier = last.non_modifier().ok_or_else(invalid_shortcut)?;
+
+        let mut modifiers = BTreeSet::new();
+
+        for token in modifier_tokens {
+            match token.parse::<KeyLayer>()? {
+                KeyLayer {
+                    keypad_state,
+                    key: Key::Modifier(modifier),
+                } if keypad_state == last.keypad_state => {
+                    modifiers.insert(modifier);
+                }
+                _ => return Err(invalid_shortcut()),
+            }
+        }
+
+        Ok(Shortcut::new(last.keypad_state, modifiers, non_modifier))
+    }
+}
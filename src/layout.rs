@@ -1,15 +1,29 @@
 use std::collections::HashMap;
 
 use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
+use crate::diff::LayoutDiff;
 use crate::keys::*;
 use crate::macros::*;
+use crate::merge::{finish, merge_maps, Conflict, MergeError, MergePolicy, Merged};
+use crate::parse::{parse_layout, ParseError};
+use crate::reachability::Reachability;
+use crate::resolve::{resolve, resolve_shortcut, Output};
+use crate::status::{status, Status};
+use crate::validate::{validate, Diagnostic, Limits};
+use crate::vdrive::{write_layout, LayoutSlot};
 
 /// `Layout` represents a keyboard layout including key remappings and macros. A `Layout`
 ///  should be constructed from a `Configuration` using the builder pattern.
-#[derive(PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Layout {
-    pub remappings: HashMap<KeyLayer, Option<KeyLayer>>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serialize::sorted"))]
+    pub remappings: HashMap<KeyLayer, Option<Target>>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serialize::sorted"))]
     pub macros: HashMap<Shortcut, MacroOutput>,
 }
 
@@ -21,46 +35,138 @@ impl fmt::Display for Layout {
         let mut mappings: Vec<String> = Vec::new();
 
         for (k, v) in remappings.iter() {
-            let value = match v {
-                None => "null".to_string(),
-                Some(key) => format!("{}", key),
-            };
-
-            mappings.push(format!("[{}]>[{}]", k, value));
+            mappings.push(remapping_line(k, v));
         }
 
         let mut macros: Vec<_> = self.macros.iter().collect();
         macros.sort_unstable();
 
         for (k, v) in macros.iter() {
-            mappings.push(format!("{}>{}", k, v).to_lowercase());
+            mappings.push(macro_line(k, v));
         }
 
         write!(f, "{}", mappings.join("\n"))
     }
 }
 
+/// Formats a remapping as a line of a layout file, e.g., `[A]>[left]`.
+pub(crate) fn remapping_line(key: &KeyLayer, value: &Option<Target>) -> String {
+    let value = match value {
+        None => "null".to_string(),
+        Some(target) => format!("{}", target),
+    };
+
+    format!("[{}]>[{}]", key, value)
+}
+
+/// Formats a macro as a line of a layout file, e.g., `{kp-c}>{w}{w}{w}`.
+pub(crate) fn macro_line(shortcut: &Shortcut, macro_output: &MacroOutput) -> String {
+    format!("{}>{}", shortcut, macro_output).to_lowercase()
+}
+
+impl Layout {
+    /// Parses a layout in the format written by `Layout`'s `Display` implementation, e.g., a
+    /// layout file copied from the keyboard. Errors report the line they occurred on.
+    pub fn parse(s: &str) -> Result<Layout, ParseError> {
+        parse_layout(s)
+    }
+
+    /// Writes the layout into a slot of the v-drive mounted at `root`, e.g., `active/1.txt` for
+    /// `LayoutSlot::Custom(1)`. Any file it replaces is first backed up. Returns the path written.
+    pub fn write_to_vdrive(&self, root: &Path, slot: LayoutSlot) -> io::Result<PathBuf> {
+        write_layout(root, slot, &format!("{}", self))
+    }
+
+    /// Compares this layout with the one in a slot of the v-drive mounted at `root`, e.g., to find
+    /// remappings and macros changed on-board since the layout was written.
+    pub fn status(&self, root: &Path, slot: LayoutSlot) -> io::Result<Status> {
+        status(self, root, slot)
+    }
+
+    /// Lists the remappings and macros which differ between this layout and `other`, treating
+    /// this layout as the old one.
+    pub fn diff(&self, other: &Layout) -> LayoutDiff {
+        LayoutDiff::new(self, other)
+    }
+
+    /// Reports the outputs which no key produces any longer and those produced by more than one
+    /// key, e.g., after remapping `A` to the left arrow without remapping another key to `A`.
+    pub fn reachability(&self) -> Reachability {
+        Reachability::new(self)
+    }
+
+    /// What `key` does in this layout, taking macros, remappings, dead keys and the factory
+    /// keypad layer into account, e.g., `kp1` for `M` in the keypad layer if it is not remapped.
+    pub fn resolve(&self, key: &KeyLayer) -> Output<'_> {
+        resolve(self, key)
+    }
+
+    /// What `shortcut` does in this layout: its macro, if any, or else its non-modifier key's
+    /// output together with what its modifier keys send.
+    pub fn resolve_shortcut(&self, shortcut: &Shortcut) -> Output<'_> {
+        resolve_shortcut(self, shortcut)
+    }
+
+    /// Checks the layout against the firmware's `limits`, e.g., `Limits::default()` for the
+    /// Advantage 2. An empty list means the keyboard will load the layout without truncating it.
+    pub fn validate(&self, limits: &Limits) -> Vec<Diagnostic> {
+        validate(self, limits)
+    }
+
+    /// Overlays `other` on this layout, e.g., personal tweaks on a team's base layout. Remappings
+    /// and macros defined differently by both layouts are resolved using `policy` and reported.
+    pub fn merge(&self, other: &Layout, policy: MergePolicy) -> Result<Merged<Layout>, MergeError> {
+        let (remappings, remapping_conflicts) =
+            merge_maps(&self.remappings, &other.remappings, policy);
+        let (macros, macro_conflicts) = merge_maps(&self.macros, &other.macros, policy);
+
+        let conflicts = remapping_conflicts
+            .into_iter()
+            .map(Conflict::Remapping)
+            .chain(macro_conflicts.into_iter().map(Conflict::Macro))
+            .collect();
+
+        finish(Layout { remappings, macros }, conflicts, policy)
+    }
+}
+
+impl FromStr for Layout {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Layout::parse(s)
+    }
+}
+
+/// The key remappings of the preset named `name`, e.g., `colemak`. Names are case-insensitive.
+pub fn preset(name: &str) -> Option<HashMap<KeyLayer, Option<Target>>> {
+    match name.to_lowercase().as_str() {
+        "colemak" => Some(colemak()),
+        _ => None,
+    }
+}
+
 /// Key remappings for the `colemak` keyboard layout.
-pub fn colemak() -> HashMap<KeyLayer, Option<KeyLayer>> {
+pub fn colemak() -> HashMap<KeyLayer, Option<Target>> {
     use self::NonModifier::*;
 
     hashmap! {
-        KeyLayer::off(Key::NonModifier(T)) => Some(KeyLayer::off(Key::NonModifier(G))),
-        KeyLayer::off(Key::NonModifier(R)) => Some(KeyLayer::off(Key::NonModifier(P))),
-        KeyLayer::off(Key::NonModifier(E)) => Some(KeyLayer::off(Key::NonModifier(F))),
-        KeyLayer::off(Key::NonModifier(G)) => Some(KeyLayer::off(Key::NonModifier(D))),
-        KeyLayer::off(Key::NonModifier(F)) => Some(KeyLayer::off(Key::NonModifier(T))),
-        KeyLayer::off(Key::NonModifier(D)) => Some(KeyLayer::off(Key::NonModifier(S))),
-        KeyLayer::off(Key::NonModifier(S)) => Some(KeyLayer::off(Key::NonModifier(R))),
-        KeyLayer::off(Key::NonModifier(Y)) => Some(KeyLayer::off(Key::NonModifier(J))),
-        KeyLayer::off(Key::NonModifier(U)) => Some(KeyLayer::off(Key::NonModifier(L))),
-        KeyLayer::off(Key::NonModifier(I)) => Some(KeyLayer::off(Key::NonModifier(U))),
-        KeyLayer::off(Key::NonModifier(O)) => Some(KeyLayer::off(Key::NonModifier(Y))),
-        KeyLayer::off(Key::NonModifier(P)) => Some(KeyLayer::off(Key::NonModifier(SemiColon))),
-        KeyLayer::off(Key::NonModifier(J)) => Some(KeyLayer::off(Key::NonModifier(N))),
-        KeyLayer::off(Key::NonModifier(K)) => Some(KeyLayer::off(Key::NonModifier(E))),
-        KeyLayer::off(Key::NonModifier(L)) => Some(KeyLayer::off(Key::NonModifier(I))),
-        KeyLayer::off(Key::NonModifier(SemiColon)) => Some(KeyLayer::off(Key::NonModifier(O))),
-        KeyLayer::off(Key::NonModifier(N)) => Some(KeyLayer::off(Key::NonModifier(K)))
+        KeyLayer::off(Key::NonModifier(T)) => Some(Key::NonModifier(G).into()),
+        KeyLayer::off(Key::NonModifier(R)) => Some(Key::NonModifier(P).into()),
+        KeyLayer::off(Key::NonModifier(E)) => Some(Key::NonModifier(F).into()),
+        KeyLayer::off(Key::NonModifier(G)) => Some(Key::NonModifier(D).into()),
+        KeyLayer::off(Key::NonModifier(F)) => Some(Key::NonModifier(T).into()),
+        KeyLayer::off(Key::NonModifier(D)) => Some(Key::NonModifier(S).into()),
+        KeyLayer::off(Key::NonModifier(S)) => Some(Key::NonModifier(R).into()),
+        KeyLayer::off(Key::NonModifier(Y)) => Some(Key::NonModifier(J).into()),
+        KeyLayer::off(Key::NonModifier(U)) => Some(Key::NonModifier(L).into()),
+        KeyLayer::off(Key::NonModifier(I)) => Some(Key::NonModifier(U).into()),
+        KeyLayer::off(Key::NonModifier(O)) => Some(Key::NonModifier(Y).into()),
+        KeyLayer::off(Key::NonModifier(P)) => Some(Key::NonModifier(SemiColon).into()),
+        KeyLayer::off(Key::NonModifier(J)) => Some(Key::NonModifier(N).into()),
+        KeyLayer::off(Key::NonModifier(K)) => Some(Key::NonModifier(E).into()),
+        KeyLayer::off(Key::NonModifier(L)) => Some(Key::NonModifier(I).into()),
+        KeyLayer::off(Key::NonModifier(SemiColon)) => Some(Key::NonModifier(O).into()),
+        KeyLayer::off(Key::NonModifier(N)) => Some(Key::NonModifier(K).into())
     }
 }
@@ -3,13 +3,31 @@
 //! This project allows keyboard layouts for the Kinesis Advantage 2 to be generated
 //! programmatically using Rust. Support for remapping keys and macros is provided.
 
-extern crate either;
 #[macro_use]
 extern crate maplit;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "toml")]
+extern crate toml;
 
 pub use maplit::btreeset;
 
+#[cfg(feature = "config")]
+pub mod config;
 pub mod configure;
+pub mod diff;
+pub mod geometry;
+pub mod info;
 pub mod keys;
 pub mod layout;
 pub mod macros;
+pub mod merge;
+pub mod parse;
+pub mod reachability;
+pub mod resolve;
+#[cfg(feature = "serde")]
+mod serialize;
+pub mod settings;
+pub mod status;
+pub mod validate;
+pub mod vdrive;
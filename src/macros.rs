@@ -1,66 +1,350 @@
+use std::collections::BTreeSet;
+use std::error;
 use std::fmt;
-
-use either::*;
+use std::str::FromStr;
 
 use crate::keys::*;
+use crate::parse::{split_tokens, ParseErrorKind};
+
+/// The longest delay a single delay token can hold, in milliseconds.
+pub const MAX_DELAY_MS: u16 = 999;
+
+/// The fastest playback speed. The slowest is 1.
+pub const MAX_SPEED: u8 = 9;
 
-/// Models the output of a macro. A macro is represented as a vector of either a vector of
-/// keypresses or a shortcut. This allows complex macros to be defined. For example, a
-/// macro which outputs some text, followed by a keyboard shortcut, followed by some more text.
+/// Models the output of a macro. A macro is represented as a vector of steps, each of which is
+/// a vector of keypresses, a shortcut, an action, a delay, a playback speed or the press or
+/// release of a modifier held across several steps. This allows complex macros to be defined. For
+/// example, a macro which outputs some text, followed by a keyboard shortcut, followed by some
+/// more text.
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Hash, Debug)]
-pub struct MacroOutput(Vec<Either<Vec<KeyPress>, Shortcut>>);
+pub struct MacroOutput(Vec<MacroStep>);
+
+/// A step of a `MacroOutput`. Unlike a `MacroComponent`, a step is system-specific.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Hash, Debug)]
+enum MacroStep {
+    KeyPresses(Vec<KeyPress>),
+    Shortcut(Shortcut),
+    Action(Action),
+    Delay(u16),
+    Speed(u8),
+    Press(Modifier),
+    Release(Modifier),
+}
 
 impl fmt::Display for MacroOutput {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut string: String = String::new();
+        for token in tokens(&self.0) {
+            write!(f, "{}", token)?;
+        }
 
-        for out in self.0.iter() {
-            match out {
-                Left(keys) => {
-                    let mut shifted = false;
+        Ok(())
+    }
+}
 
-                    for k in keys.iter() {
-                        if !shifted && k.shifted {
-                            shifted = true;
-                            string.push_str(format!("{{-{}}}", Modifier::LeftShift).as_str());
-                        }
+impl MacroOutput {
+    /// The number of tokens in the macro, i.e., the key presses and releases the keyboard
+    /// stores for it.
+    pub fn token_count(&self) -> usize {
+        format!("{}", self).matches('{').count()
+    }
 
-                        if shifted && !k.shifted {
-                            shifted = false;
-                            string.push_str(format!("{{+{}}}", Modifier::LeftShift).as_str());
-                        }
+    /// Converts a `MacroOutput` back to a system agnostic `MacroOutputTemp`. Shortcuts are kept
+    /// as shortcuts, as the `Command` they were created from, if any, cannot be recovered.
+    pub fn to_macro_output_temp(&self) -> MacroOutputTemp {
+        MacroOutputTemp(
+            self.0
+                .iter()
+                .map(|out| match out {
+                    MacroStep::KeyPresses(presses) => MacroComponent::KeyPresses(presses.clone()),
+                    MacroStep::Shortcut(shortcut) => MacroComponent::Shortcut(shortcut.clone()),
+                    MacroStep::Action(action) => MacroComponent::Action(*action),
+                    MacroStep::Delay(ms) => MacroComponent::Delay(*ms),
+                    MacroStep::Speed(speed) => MacroComponent::Speed(*speed),
+                    MacroStep::Press(key) => MacroComponent::Press(*key),
+                    MacroStep::Release(key) => MacroComponent::Release(*key),
+                })
+                .collect(),
+        )
+    }
+}
 
-                        string.push_str(format!("{{{}}}", k.key).to_lowercase().as_str());
-                    }
+impl FromStr for MacroOutput {
+    type Err = ParseErrorKind;
+
+    /// Parses macro output tokens. A run of keys wrapped in `{-lshift}`/`{+lshift}` is read back
+    /// as shifted key presses, which is how upper case letters and symbols are written. Modifiers
+    /// wrapping a single key are read back as a `Shortcut`, and any other modifier as a press and
+    /// a release, which must follow it.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = split_tokens(s)?
+            .into_iter()
+            .map(str::parse)
+            .collect::<Result<Vec<Token>, _>>()?;
+
+        normalize(tokens)
+            .map(MacroOutput)
+            .map_err(ParseErrorKind::InvalidMacro)
+    }
+}
+
+/// A single token of a macro's output, i.e., a key press or release the keyboard stores.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum Token {
+    Press(Modifier),
+    Release(Modifier),
+    Key(Keypad, NonModifier),
+    Action(Action),
+    Delay(u16),
+    Speed(u8),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Press(modifier) => write!(f, "{{-{}}}", modifier),
+            Token::Release(modifier) => write!(f, "{{+{}}}", modifier),
+            Token::Key(keypad, key) => write!(
+                f,
+                "{{{}}}",
+                format!("{}", KeyLayer::new(*keypad, Key::NonModifier(*key))).to_lowercase()
+            ),
+            Token::Action(action) => write!(f, "{{{}}}", action),
+            Token::Delay(ms) => write!(f, "{{d{}}}", ms),
+            Token::Speed(speed) => write!(f, "{{s{}}}", speed),
+        }
+    }
+}
+
+impl FromStr for Token {
+    type Err = ParseErrorKind;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(modifier) = s.strip_prefix('-') {
+            return modifier.parse().map(Token::Press);
+        }
+
+        if let Some(modifier) = s.strip_prefix('+') {
+            return modifier.parse().map(Token::Release);
+        }
+
+        if let Some(token) = playback_token(s) {
+            return token.map_err(ParseErrorKind::InvalidMacro);
+        }
+
+        match s.parse()? {
+            Target::Key(key) => match key.non_modifier() {
+                Some(non_modifier) => Ok(Token::Key(key.keypad_state(), non_modifier)),
+                None => Err(ParseErrorKind::UnknownToken(s.to_string())),
+            },
+            Target::Action(action) => Ok(Token::Action(action)),
+        }
+    }
+}
 
-                    if shifted {
-                        string.push_str(format!("{{+{}}}", Modifier::LeftShift).as_str());
+/// The tokens the keyboard stores for `steps`. A shortcut's modifier, or the shift of a shifted
+/// key press, which is already held by a `MacroStep::Press` is not pressed again, so that it stays
+/// held until its own release.
+fn tokens(steps: &[MacroStep]) -> Vec<Token> {
+    use crate::keys::Modifier::LeftShift;
+
+    let mut tokens = Vec::new();
+    let mut held = BTreeSet::new();
+
+    for step in steps.iter() {
+        match step {
+            MacroStep::KeyPresses(key_presses) => {
+                let shift = !held.contains(&LeftShift);
+                let mut shifted = false;
+
+                for key_press in key_presses.iter() {
+                    if shift && key_press.shifted != shifted {
+                        shifted = key_press.shifted;
+                        tokens.push(match shifted {
+                            true => Token::Press(LeftShift),
+                            false => Token::Release(LeftShift),
+                        });
                     }
+
+                    tokens.push(Token::Key(Keypad::Off, key_press.key));
                 }
 
-                Right(shortcut) => {
-                    let mut temp_string = String::new();
-                    let keypad = shortcut.keypad;
-
-                    temp_string.push_str(
-                        format!(
-                            "{{{}}}",
-                            KeyLayer::new(keypad, Key::NonModifier(shortcut.non_modifier))
-                        )
-                        .as_str(),
-                    );
-
-                    for key in shortcut.modifiers.iter() {
-                        temp_string.insert_str(0, format!("{{-{}}}", key).as_str());
-                        temp_string.push_str(format!("{{+{}}}", key).as_str());
-                    }
+                if shifted {
+                    tokens.push(Token::Release(LeftShift));
+                }
+            }
+            MacroStep::Shortcut(shortcut) => {
+                let modifiers: Vec<Modifier> =
+                    shortcut.modifiers.difference(&held).cloned().collect();
+
+                tokens.extend(modifiers.iter().rev().map(|m| Token::Press(*m)));
+                tokens.push(Token::Key(shortcut.keypad, shortcut.non_modifier));
+                tokens.extend(modifiers.iter().map(|m| Token::Release(*m)));
+            }
+            MacroStep::Action(action) => tokens.push(Token::Action(*action)),
+            MacroStep::Delay(ms) => tokens.push(Token::Delay(*ms)),
+            MacroStep::Speed(speed) => tokens.push(Token::Speed(*speed)),
+            MacroStep::Press(modifier) => {
+                held.insert(*modifier);
+                tokens.push(Token::Press(*modifier));
+            }
+            MacroStep::Release(modifier) => {
+                held.remove(modifier);
+                tokens.push(Token::Release(*modifier));
+            }
+        }
+    }
+
+    tokens
+}
+
+/// Groups `tokens` into steps the way a layout file is read back, so that equal tokens always
+/// give equal steps: keys wrapped in `lshift` become shifted key presses and modifiers wrapped
+/// around a single key a shortcut, while any other modifier is pressed and released as a step of
+/// its own. Fails unless every modifier pressed is released, without being pressed again first.
+fn normalize(tokens: Vec<Token>) -> Result<Vec<MacroStep>, MacroError> {
+    let mut output: Vec<MacroStep> = Vec::new();
+    let mut pressed: Vec<Modifier> = Vec::new();
+    let mut tokens = tokens.into_iter().peekable();
+
+    while tokens.peek().is_some() {
+        let mut held: Vec<Modifier> = Vec::new();
+
+        while let Some(Token::Press(modifier)) = tokens.peek() {
+            held.push(*modifier);
+            tokens.next();
+        }
+
+        let mut keys = Vec::new();
+
+        while let Some(Token::Key(keypad, key)) = tokens.peek() {
+            keys.push((*keypad, *key));
+            tokens.next();
+        }
 
-                    string.push_str(temp_string.as_str());
+        let step = match tokens.peek() {
+            _ if !held.is_empty() || !keys.is_empty() => None,
+            Some(Token::Action(action)) => Some(MacroStep::Action(*action)),
+            Some(Token::Delay(ms)) => Some(MacroStep::Delay(*ms)),
+            Some(Token::Speed(speed)) => Some(MacroStep::Speed(*speed)),
+            _ => None,
+        };
+
+        if let Some(step) = step {
+            output.push(step);
+            tokens.next();
+            continue;
+        }
+
+        let mut released: Vec<Modifier> = Vec::new();
+
+        while let Some(Token::Release(modifier)) = tokens.peek() {
+            released.push(*modifier);
+            tokens.next();
+        }
+
+        let modifiers: BTreeSet<Modifier> = held.iter().cloned().collect();
+        let balanced = !held.is_empty()
+            && held.len() == modifiers.len()
+            && released.len() == modifiers.len()
+            && released.iter().all(|m| modifiers.contains(m))
+            && !pressed.iter().any(|m| modifiers.contains(m));
+        let shifted = balanced
+            && held == [Modifier::LeftShift]
+            && !keys.is_empty()
+            && keys.iter().all(|(keypad, _)| *keypad == Keypad::Off);
+
+        match keys.as_slice() {
+            _ if shifted => {
+                for (_, key) in keys {
+                    push_key_press(&mut output, KeyPress::shifted(key));
                 }
+                continue;
             }
+            [(Keypad::Off, key)] if balanced => {
+                output.push(MacroStep::Shortcut(Shortcut::keypad_off(modifiers, *key)));
+                continue;
+            }
+            [(Keypad::On, key)] if balanced => {
+                output.push(MacroStep::Shortcut(Shortcut::keypad_on(modifiers, *key)));
+                continue;
+            }
+            _ => {}
         }
 
-        write!(f, "{}", string)
+        for modifier in held {
+            if pressed.contains(&modifier) {
+                return Err(MacroError::UnreleasedModifier(modifier));
+            }
+
+            pressed.push(modifier);
+            output.push(MacroStep::Press(modifier));
+        }
+
+        for (keypad, key) in keys {
+            match keypad {
+                Keypad::Off => push_key_press(&mut output, KeyPress::not_shifted(key)),
+                Keypad::On => output.push(MacroStep::Shortcut(Shortcut::keypad_on(
+                    BTreeSet::new(),
+                    key,
+                ))),
+            }
+        }
+
+        for modifier in released {
+            match pressed.iter().position(|m| *m == modifier) {
+                Some(index) => pressed.remove(index),
+                None => return Err(MacroError::UnpressedModifier(modifier)),
+            };
+
+            output.push(MacroStep::Release(modifier));
+        }
+    }
+
+    match pressed.first() {
+        Some(modifier) => Err(MacroError::UnreleasedModifier(*modifier)),
+        None => Ok(output),
+    }
+}
+
+/// Parses a delay token, e.g., `d125`, or a playback speed token, e.g., `s5`. Returns `None` for
+/// any other token. A value too large to parse is out of range, like any other invalid value.
+fn playback_token(token: &str) -> Option<Result<Token, MacroError>> {
+    let (kind, value) = token.split_at(token.char_indices().nth(1)?.0);
+
+    if !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let value = value.parse().unwrap_or(u32::MAX);
+
+    match kind {
+        "d" | "D" => Some(check_delay(value).map(Token::Delay)),
+        "s" | "S" => Some(check_speed(value).map(Token::Speed)),
+        _ => None,
+    }
+}
+
+fn check_delay(ms: u32) -> Result<u16, MacroError> {
+    if (1..=u32::from(MAX_DELAY_MS)).contains(&ms) {
+        Ok(ms as u16)
+    } else {
+        Err(MacroError::InvalidDelay(ms))
+    }
+}
+
+fn check_speed(speed: u32) -> Result<u8, MacroError> {
+    if (1..=u32::from(MAX_SPEED)).contains(&speed) {
+        Ok(speed as u8)
+    } else {
+        Err(MacroError::InvalidSpeed(speed))
+    }
+}
+
+fn push_key_press(output: &mut Vec<MacroStep>, key_press: KeyPress) {
+    match output.last_mut() {
+        Some(MacroStep::KeyPresses(key_presses)) => key_presses.push(key_press),
+        _ => output.push(MacroStep::KeyPresses(vec![key_press])),
     }
 }
 
@@ -75,16 +359,19 @@ impl MacroBuilder {
         Default::default()
     }
 
-    /// Creates an `MacroBuilder` from a string literal.
-    pub fn from_string(s: &str) -> MacroBuilder {
-        MacroBuilder(vec![MacroComponent::KeyPresses(string_to_key_presses(s))])
+    /// Creates an `MacroBuilder` from a string literal. Fails if the string contains a
+    /// character which cannot be typed, i.e., one which is not printable ASCII, a tab or a newline.
+    pub fn from_string(s: &str) -> Result<MacroBuilder, MacroError> {
+        Ok(MacroBuilder(vec![MacroComponent::KeyPresses(
+            string_to_key_presses(s)?,
+        )]))
     }
 
-    /// Adds a string literal.
-    pub fn with_string(&mut self, s: &str) -> &mut MacroBuilder {
+    /// Adds a string literal. Fails if the string contains a character which cannot be typed.
+    pub fn with_string(&mut self, s: &str) -> Result<&mut MacroBuilder, MacroError> {
         self.0
-            .push(MacroComponent::KeyPresses(string_to_key_presses(s)));
-        self
+            .push(MacroComponent::KeyPresses(string_to_key_presses(s)?));
+        Ok(self)
     }
 
     /// Adds a shortcut.
@@ -137,6 +424,71 @@ impl MacroBuilder {
         self
     }
 
+    /// Adds an action, e.g., muting the volume or a mouse click.
+    pub fn with_action(&mut self, action: Action) -> &mut MacroBuilder {
+        self.0.push(MacroComponent::Action(action));
+        self
+    }
+
+    /// Pauses playback for `ms` milliseconds, e.g., to give an application time to open. Fails
+    /// unless the delay is from 1 to `MAX_DELAY_MS`; longer pauses need several delays.
+    pub fn delay(&mut self, ms: u16) -> Result<&mut MacroBuilder, MacroError> {
+        self.0.push(MacroComponent::Delay(check_delay(ms.into())?));
+        Ok(self)
+    }
+
+    /// Sets the speed the macro is played back at, from 1, the slowest, to `MAX_SPEED`, replacing
+    /// any speed set before. Without a speed the keyboard's global macro speed is used.
+    pub fn speed(&mut self, speed: u8) -> Result<&mut MacroBuilder, MacroError> {
+        let speed = check_speed(speed.into())?;
+
+        self.0
+            .retain(|component| !matches!(component, MacroComponent::Speed(_)));
+        self.0.insert(0, MacroComponent::Speed(speed));
+        Ok(self)
+    }
+
+    /// Presses and holds a modifier until it is released with `release`, e.g., to hold alt while
+    /// pressing tab several times. `Configure::make` reports a modifier which is not released.
+    pub fn press(&mut self, modifier: Modifier) -> &mut MacroBuilder {
+        self.0.push(MacroComponent::Press(modifier));
+        self
+    }
+
+    /// Releases a modifier held with `press`.
+    pub fn release(&mut self, modifier: Modifier) -> &mut MacroBuilder {
+        self.0.push(MacroComponent::Release(modifier));
+        self
+    }
+
+    /// Holds `modifiers` while the steps added by `steps` are played, e.g., holding shift while
+    /// moving the cursor down to select lines. A step which uses a held modifier itself, e.g.,
+    /// `Command::Copy` while holding `lctrl`, does not press it again. Fails if `steps` does,
+    /// leaving the builder as it was.
+    pub fn holding<F>(
+        &mut self,
+        modifiers: BTreeSet<Modifier>,
+        steps: F,
+    ) -> Result<&mut MacroBuilder, MacroError>
+    where
+        F: FnOnce(&mut MacroBuilder) -> Result<(), MacroError>,
+    {
+        let mut held = MacroBuilder::new();
+
+        for modifier in modifiers.iter() {
+            held.press(*modifier);
+        }
+
+        steps(&mut held)?;
+
+        for modifier in modifiers.iter().rev() {
+            held.release(*modifier);
+        }
+
+        self.0.append(&mut held.0);
+        Ok(self)
+    }
+
     /// Adds a system-agnostic command.
     pub fn with_command(&mut self, command: Command) -> &mut MacroBuilder {
         self.0.push(MacroComponent::Command(command));
@@ -149,10 +501,10 @@ impl MacroBuilder {
     }
 }
 
-fn char_to_key(c: char) -> NonModifier {
+fn char_to_key(c: char) -> Option<NonModifier> {
     use self::NonModifier::*;
 
-    match c {
+    let key = match c {
         '=' | '+' => Equals,
         '1' | '!' => One,
         '2' | '@' => Two,
@@ -163,6 +515,7 @@ fn char_to_key(c: char) -> NonModifier {
         '7' | '&' => Seven,
         '8' | '*' => Eight,
         '9' | '(' => Nine,
+        '0' | ')' => Zero,
         '-' | '_' => Hyphen,
         'q' | 'Q' => Q,
         'w' | 'W' => W,
@@ -202,27 +555,92 @@ fn char_to_key(c: char) -> NonModifier {
         '`' | '~' => Backtick,
         '\n' => Enter,
         ' ' => Space,
-        c => panic!("Oh No".to_owned() + format!("{}", c).as_str()),
-    }
+        _ => return None,
+    };
+
+    Some(key)
 }
 
 fn requires_shift(c: char) -> bool {
     let shifted_symbols = [
-        '+', '!', '@', '#', '$', '%', '^', '&', '*', '(', '_', '|', '"', '<', '>', '?', '{', '}',
+        '+', '!', '@', '#', '$', '%', '^', '&', '*', '(', ')', '_', '|', ':', '"', '<', '>', '?',
+        '{', '}', '~',
     ];
 
     c.is_ascii_uppercase() || shifted_symbols.contains(&c)
 }
 
-fn string_to_key_presses(s: &str) -> Vec<KeyPress> {
+fn string_to_key_presses(s: &str) -> Result<Vec<KeyPress>, MacroError> {
     s.chars()
-        .map(|c| KeyPress::new(requires_shift(c), char_to_key(c)))
+        .enumerate()
+        .map(|(position, c)| match char_to_key(c) {
+            Some(key) => Ok(KeyPress::new(requires_shift(c), key)),
+            None => Err(MacroError::UnsupportedCharacter {
+                character: c,
+                position,
+            }),
+        })
         .collect()
 }
 
+/// An error encountered while building a macro.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Hash, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum MacroError {
+    /// A character of a string cannot be typed. `position` counts characters from zero.
+    UnsupportedCharacter { character: char, position: usize },
+    /// A delay, in milliseconds, is not from 1 to `MAX_DELAY_MS`.
+    InvalidDelay(u32),
+    /// A playback speed is not from 1 to `MAX_SPEED`.
+    InvalidSpeed(u32),
+    /// A modifier is pressed without being released, or pressed again while held.
+    UnreleasedModifier(Modifier),
+    /// A modifier is released without being held.
+    UnpressedModifier(Modifier),
+}
+
+impl fmt::Display for MacroError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MacroError::UnsupportedCharacter {
+                character,
+                position,
+            } => write!(
+                f,
+                "unsupported character {:?} at position {}",
+                character, position
+            ),
+            MacroError::InvalidDelay(ms) => {
+                write!(f, "delay of {}ms is not from 1 to {}ms", ms, MAX_DELAY_MS)
+            }
+            MacroError::InvalidSpeed(speed) => {
+                write!(f, "speed {} is not from 1 to {}", speed, MAX_SPEED)
+            }
+            MacroError::UnreleasedModifier(modifier) => {
+                write!(f, "`{}` is pressed without being released", modifier)
+            }
+            MacroError::UnpressedModifier(modifier) => {
+                write!(f, "`{}` is released without being pressed", modifier)
+            }
+        }
+    }
+}
+
+impl error::Error for MacroError {}
+
 /// Indicates whether the keyboard layout will be used with a `PC` or `Mac`.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Debug)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 pub enum System {
+    #[default]
     PC,
     Windows,
     Mac,
@@ -235,14 +653,13 @@ impl System {
     }
 }
 
-impl Default for System {
-    fn default() -> Self {
-        System::PC
-    }
-}
-
 /// Commands are system-agnostic actions.
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum Command {
     Copy,
     Paste,
@@ -315,30 +732,81 @@ impl Command {
 /// Newtype wrapping a vector of `MacroOutputTemp`. This datatype represents system agnostic
 /// macro components.
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Hash, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct MacroOutputTemp(Vec<MacroComponent>);
 
 impl MacroOutputTemp {
-    pub fn to_macro_output(&self, system: System) -> MacroOutput {
-        MacroOutput(self.0.iter().map(|x| x.to_either(system)).collect())
+    /// Converts the components to steps for `system`. The steps are normalized to the ones a
+    /// layout file is read back as, e.g., a modifier pressed and released around a single key
+    /// becomes a shortcut, and `lshift` held around letters becomes shifted key presses, so that
+    /// a layout made with `Configure::make` is equal to the one parsed from its output. Fails if
+    /// a modifier is pressed without being released or released without being pressed.
+    pub fn to_macro_output(&self, system: System) -> Result<MacroOutput, MacroError> {
+        let steps: Vec<MacroStep> = self.0.iter().map(|x| x.to_step(system)).collect();
+
+        normalize(tokens(&steps)).map(MacroOutput)
+    }
+
+    /// Checks that every modifier pressed with `MacroBuilder::press` is later released, and that
+    /// only held modifiers are released.
+    pub fn check_held_modifiers(&self) -> Result<(), MacroError> {
+        let mut held = BTreeSet::new();
+
+        for component in self.0.iter() {
+            match component {
+                MacroComponent::Press(modifier) if !held.insert(*modifier) => {
+                    return Err(MacroError::UnreleasedModifier(*modifier))
+                }
+                MacroComponent::Release(modifier) if !held.remove(modifier) => {
+                    return Err(MacroError::UnpressedModifier(*modifier))
+                }
+                _ => {}
+            }
+        }
+
+        match held.into_iter().next() {
+            Some(modifier) => Err(MacroError::UnreleasedModifier(modifier)),
+            None => Ok(()),
+        }
     }
 }
 
-/// A `MacroComponent` is either a vector of keypresses, shortcut or a command. `MacroComponent`
-/// is system agnostic.
+/// A `MacroComponent` is either a vector of keypresses, shortcut, action, command, delay in
+/// milliseconds, playback speed, or the press or release of a held modifier.
+/// `MacroComponent` is system agnostic.
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Hash, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum MacroComponent {
     KeyPresses(Vec<KeyPress>),
     Shortcut(Shortcut),
+    Action(Action),
     Command(Command),
+    Delay(u16),
+    Speed(u8),
+    Press(Modifier),
+    Release(Modifier),
 }
 
 impl MacroComponent {
     /// Converts system agnostic commands to system-specific keyboard shortcuts.
-    fn to_either(&self, system: System) -> Either<Vec<KeyPress>, Shortcut> {
+    fn to_step(&self, system: System) -> MacroStep {
         match self {
-            MacroComponent::KeyPresses(presses) => Left(presses.clone()),
-            MacroComponent::Shortcut(shortcut) => Right(shortcut.clone()),
-            MacroComponent::Command(command) => Right(command.to_shortcut(system)),
+            MacroComponent::KeyPresses(presses) => MacroStep::KeyPresses(presses.clone()),
+            MacroComponent::Shortcut(shortcut) => MacroStep::Shortcut(shortcut.clone()),
+            MacroComponent::Action(action) => MacroStep::Action(*action),
+            MacroComponent::Command(command) => MacroStep::Shortcut(command.to_shortcut(system)),
+            MacroComponent::Delay(ms) => MacroStep::Delay(*ms),
+            MacroComponent::Speed(speed) => MacroStep::Speed(*speed),
+            MacroComponent::Press(key) => MacroStep::Press(*key),
+            MacroComponent::Release(key) => MacroStep::Release(*key),
         }
     }
 }
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::hash::Hash;
+
+use crate::keys::*;
+
+/// Selects how a merge resolves a remapping or macro defined differently on both sides.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Debug)]
+pub enum MergePolicy {
+    /// Keep the value of the value being merged into.
+    PreferLeft,
+    /// Keep the value of the value being merged in.
+    PreferRight,
+    /// Fail the merge, reporting every conflict.
+    Error,
+}
+
+/// A remapped key, macro shortcut or system which both sides of a merge define differently.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Hash, Debug)]
+pub enum Conflict {
+    Remapping(KeyLayer),
+    Macro(Shortcut),
+    System,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Conflict::Remapping(key) => write!(f, "remapping of [{}]", key),
+            Conflict::Macro(shortcut) => write!(f, "macro for {}", shortcut),
+            Conflict::System => write!(f, "system"),
+        }
+    }
+}
+
+/// The result of a successful merge. `conflicts` lists every conflict which was resolved using
+/// the `MergePolicy`.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Merged<T> {
+    pub merged: T,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Returned by a merge using `MergePolicy::Error` when there are conflicts.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct MergeError {
+    pub conflicts: Vec<Conflict>,
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let conflicts: Vec<String> = self.conflicts.iter().map(|c| format!("{}", c)).collect();
+
+        write!(f, "conflicting {}", conflicts.join(", "))
+    }
+}
+
+impl error::Error for MergeError {}
+
+/// Overlays `right` on `left` following `policy`. Returns the merged map and the keys defined
+/// differently by both, in order.
+pub(crate) fn merge_maps<K, V>(
+    left: &HashMap<K, V>,
+    right: &HashMap<K, V>,
+    policy: MergePolicy,
+) -> (HashMap<K, V>, Vec<K>)
+where
+    K: Ord + Hash + Clone,
+    V: PartialEq + Clone,
+{
+    let mut merged = left.clone();
+    let mut conflicts = Vec::new();
+
+    for (key, value) in right.iter() {
+        match left.get(key) {
+            Some(left_value) if left_value != value => {
+                conflicts.push(key.clone());
+
+                if policy == MergePolicy::PreferRight {
+                    merged.insert(key.clone(), value.clone());
+                }
+            }
+            _ => {
+                merged.insert(key.clone(), value.clone());
+            }
+        }
+    }
+
+    conflicts.sort_unstable();
+
+    (merged, conflicts)
+}
+
+/// Checks the conflicts found by a merge against `policy`.
+pub(crate) fn finish<T>(
+    merged: T,
+    conflicts: Vec<Conflict>,
+    policy: MergePolicy,
+) -> Result<Merged<T>, MergeError> {
+    if policy == MergePolicy::Error && !conflicts.is_empty() {
+        Err(MergeError { conflicts })
+    } else {
+        Ok(Merged { merged, conflicts })
+    }
+}
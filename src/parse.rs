@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+
+use crate::keys::*;
+use crate::layout::Layout;
+use crate::macros::*;
+
+/// The reason a token, shortcut, macro or line of a layout, settings or config file could not be
+/// parsed.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum ParseErrorKind {
+    /// A token does not name a known key.
+    UnknownToken(String),
+    /// The line is neither a remapping (`[a]>[b]`) nor a macro (`{a}>{b}`).
+    InvalidLine(String),
+    /// The text is not a sequence of tokens in braces, e.g., `{a}{b}`.
+    InvalidTokens(String),
+    /// A macro trigger is not a set of modifiers followed by a single non-modifier key.
+    InvalidShortcut(String),
+    /// The same key is remapped more than once.
+    DuplicateRemapping(KeyLayer),
+    /// An action, which is not a key, is remapped.
+    RemappedAction(Action),
+    /// The same shortcut triggers more than one macro.
+    DuplicateMacro(Shortcut),
+    /// A setting in a settings file has a value it cannot take.
+    InvalidSetting(String, String),
+    /// A config file is not valid TOML or does not follow the config file format.
+    InvalidConfig(String),
+    /// A macro in a config file could not be built, or a macro's delay or speed is out of range.
+    InvalidMacro(MacroError),
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::ParseErrorKind::*;
+
+        match self {
+            UnknownToken(token) => write!(f, "unknown token `{}`", token),
+            InvalidLine(line) => write!(f, "invalid line `{}`", line),
+            InvalidTokens(tokens) => write!(f, "invalid tokens `{}`", tokens),
+            InvalidShortcut(shortcut) => write!(f, "invalid shortcut `{}`", shortcut),
+            DuplicateRemapping(key) => write!(f, "duplicate remapping of `{}`", key),
+            RemappedAction(action) => {
+                write!(f, "`{}` is an action and cannot be remapped", action)
+            }
+            DuplicateMacro(shortcut) => write!(f, "duplicate macro for `{}`", shortcut),
+            InvalidSetting(name, value) => write!(f, "invalid value `{}` for `{}`", value, name),
+            InvalidConfig(message) => write!(f, "invalid config: {}", message),
+            InvalidMacro(error) => write!(f, "invalid macro: {}", error),
+        }
+    }
+}
+
+impl error::Error for ParseErrorKind {}
+
+/// An error encountered while parsing a layout, settings or config file. `line` is one-based.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind)
+    }
+}
+
+impl error::Error for ParseError {}
+
+/// Parses the text format produced by `Layout`'s `Display` implementation.
+pub fn parse_layout(s: &str) -> Result<Layout, ParseError> {
+    let mut remappings = HashMap::new();
+    let mut macros = HashMap::new();
+
+    for (index, line) in s.lines().enumerate() {
+        let line = line.trim();
+        let error = |kind| ParseError {
+            line: index + 1,
+            kind,
+        };
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with('[') {
+            let (key, value) = parse_remapping(line).map_err(error)?;
+
+            if remappings.insert(key.clone(), value).is_some() {
+                return Err(error(ParseErrorKind::DuplicateRemapping(key)));
+            }
+        } else if line.starts_with('{') {
+            let (shortcut, output) = parse_macro(line).map_err(error)?;
+
+            if macros.insert(shortcut.clone(), output).is_some() {
+                return Err(error(ParseErrorKind::DuplicateMacro(shortcut)));
+            }
+        } else {
+            return Err(error(ParseErrorKind::InvalidLine(line.to_string())));
+        }
+    }
+
+    Ok(Layout { remappings, macros })
+}
+
+fn parse_remapping(line: &str) -> Result<(KeyLayer, Option<Target>), ParseErrorKind> {
+    let (key, value) = line
+        .strip_prefix('[')
+        .and_then(|l| l.strip_suffix(']'))
+        .and_then(|l| l.split_once("]>["))
+        .ok_or_else(|| ParseErrorKind::InvalidLine(line.to_string()))?;
+
+    let key: KeyLayer = match key.parse() {
+        Ok(Target::Key(key)) => key,
+        Ok(Target::Action(action)) => return Err(ParseErrorKind::RemappedAction(action)),
+        Err(error) => return Err(error),
+    };
+
+    let value = if value.eq_ignore_ascii_case("null") {
+        None
+    } else {
+        Some(value.parse()?)
+    };
+
+    Ok((key, value))
+}
+
+fn parse_macro(line: &str) -> Result<(Shortcut, MacroOutput), ParseErrorKind> {
+    let (shortcut, output) = line
+        .split_once("}>{")
+        .ok_or_else(|| ParseErrorKind::InvalidLine(line.to_string()))?;
+
+    Ok((
+        format!("{}}}", shortcut).parse()?,
+        format!("{{{}", output).parse()?,
+    ))
+}
+
+/// Splits a string of the form `{a}{b}{c}` into its tokens.
+pub(crate) fn split_tokens(s: &str) -> Result<Vec<&str>, ParseErrorKind> {
+    let mut tokens = Vec::new();
+    let mut rest = s;
+
+    while !rest.is_empty() {
+        let (token, tail) = rest
+            .strip_prefix('{')
+            .and_then(|r| r.split_once('}'))
+            .ok_or_else(|| ParseErrorKind::InvalidTokens(s.to_string()))?;
+
+        tokens.push(token);
+        rest = tail;
+    }
+
+    Ok(tokens)
+}
@@ -0,0 +1,192 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::keys::*;
+use crate::layout::Layout;
+
+/// A remapping which would make an unreachable output reachable again, i.e., `[key]>[output]`.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Hash, Debug)]
+pub struct Suggestion {
+    pub key: KeyLayer,
+    pub output: KeyLayer,
+}
+
+impl fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}]>[{}]", self.key, self.output)
+    }
+}
+
+/// Describes how far a layout's remappings are from being a permutation of the keys. A key which
+/// is not remapped produces itself, a dead key, or a key remapped to an action, produces nothing.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Reachability {
+    /// Outputs which no key produces any longer, in order.
+    pub unreachable: Vec<KeyLayer>,
+    /// Outputs produced by more than one key, with the keys producing them.
+    pub duplicated: BTreeMap<KeyLayer, BTreeSet<KeyLayer>>,
+    /// Remappings of keys whose output is duplicated onto unreachable outputs of the same layer.
+    pub suggestions: Vec<Suggestion>,
+}
+
+impl Reachability {
+    /// Analyses the remappings of `layout`.
+    pub fn new(layout: &Layout) -> Reachability {
+        let remappings = &layout.remappings;
+        let mut producers: BTreeMap<KeyLayer, BTreeSet<KeyLayer>> = BTreeMap::new();
+
+        for (key, output) in remappings.iter() {
+            if let Some(Target::Key(output)) = output {
+                producers
+                    .entry(output.clone())
+                    .or_default()
+                    .insert(key.clone());
+            }
+        }
+
+        for (output, keys) in producers.iter_mut() {
+            if !remappings.contains_key(output) {
+                keys.insert(output.clone());
+            }
+        }
+
+        let mut unreachable: Vec<KeyLayer> = remappings
+            .keys()
+            .filter(|key| !producers.contains_key(key))
+            .cloned()
+            .collect();
+        unreachable.sort_unstable();
+
+        let duplicated: BTreeMap<KeyLayer, BTreeSet<KeyLayer>> = producers
+            .iter()
+            .filter(|(_, keys)| keys.len() > 1)
+            .map(|(output, keys)| (output.clone(), keys.clone()))
+            .collect();
+
+        let spare: Vec<&KeyLayer> = duplicated
+            .iter()
+            .filter(|(output, keys)| keys.contains(output))
+            .map(|(output, _)| output)
+            .collect();
+
+        let mut suggestions = Vec::new();
+        let mut unused: Vec<&KeyLayer> = Vec::new();
+        let mut remaining: BTreeSet<&KeyLayer> = unreachable.iter().collect();
+
+        // A spare key is best remapped onto the output lost at the start of the chain of
+        // remappings which displaced it, e.g., `[left]>[A]` after `[A]>[left]`.
+        for key in spare {
+            match chain_start(key, &producers, &remaining) {
+                Some(output) => {
+                    remaining.remove(output);
+                    suggestions.push(Suggestion {
+                        key: key.clone(),
+                        output: output.clone(),
+                    });
+                }
+                None => unused.push(key),
+            }
+        }
+
+        for keypad in [Keypad::Off, Keypad::On] {
+            let keys = unused.iter().filter(|key| key.keypad_state() == keypad);
+            let outputs = remaining.iter().filter(|key| key.keypad_state() == keypad);
+
+            suggestions.extend(keys.zip(outputs).map(|(key, output)| Suggestion {
+                key: (*key).clone(),
+                output: (*output).clone(),
+            }));
+        }
+
+        suggestions.sort_unstable();
+
+        Reachability {
+            unreachable,
+            duplicated,
+            suggestions,
+        }
+    }
+
+    /// Indicates whether every output is produced by exactly one key.
+    pub fn is_permutation(&self) -> bool {
+        self.unreachable.is_empty() && self.duplicated.is_empty()
+    }
+
+    /// The unreachable outputs in the given layer.
+    pub fn unreachable_in(&self, keypad: Keypad) -> impl Iterator<Item = &KeyLayer> {
+        self.unreachable
+            .iter()
+            .filter(move |key| key.keypad_state() == keypad)
+    }
+
+    /// The duplicated outputs in the given layer, with the keys producing them.
+    pub fn duplicated_in(
+        &self,
+        keypad: Keypad,
+    ) -> impl Iterator<Item = (&KeyLayer, &BTreeSet<KeyLayer>)> {
+        self.duplicated
+            .iter()
+            .filter(move |(output, _)| output.keypad_state() == keypad)
+    }
+}
+
+impl fmt::Display for Reachability {
+    /// Lists the problems per layer, e.g., `unreachable [A]`, `[left] produced by [A], [left]`
+    /// and `suggest [left]>[A]`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut lines: Vec<String> = Vec::new();
+
+        for (keypad, heading) in [(Keypad::Off, "top layer:"), (Keypad::On, "keypad layer:")] {
+            let mut layer_lines: Vec<String> = self
+                .unreachable_in(keypad)
+                .map(|key| format!("unreachable [{}]", key))
+                .collect();
+
+            layer_lines.extend(self.duplicated_in(keypad).map(|(output, keys)| {
+                let keys: Vec<String> = keys.iter().map(|key| format!("[{}]", key)).collect();
+                format!("[{}] produced by {}", output, keys.join(", "))
+            }));
+
+            layer_lines.extend(
+                self.suggestions
+                    .iter()
+                    .filter(|suggestion| suggestion.output.keypad_state() == keypad)
+                    .map(|suggestion| format!("suggest {}", suggestion)),
+            );
+
+            if !layer_lines.is_empty() {
+                lines.push(heading.to_string());
+                lines.extend(layer_lines);
+            }
+        }
+
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Follows the remappings producing `output` backwards to the first unreachable output in
+/// `unreachable` of the same layer.
+fn chain_start<'a>(
+    output: &KeyLayer,
+    producers: &'a BTreeMap<KeyLayer, BTreeSet<KeyLayer>>,
+    unreachable: &BTreeSet<&'a KeyLayer>,
+) -> Option<&'a KeyLayer> {
+    let mut visited: BTreeSet<&KeyLayer> = BTreeSet::new();
+    let mut queue: Vec<&KeyLayer> = producers.get(output)?.iter().collect();
+
+    while let Some(key) = queue.pop() {
+        if key == output || !visited.insert(key) || key.keypad_state() != output.keypad_state() {
+            continue;
+        }
+
+        if let Some(start) = unreachable.get(key) {
+            return Some(*start);
+        }
+
+        if let Some(keys) = producers.get(key) {
+            queue.extend(keys.iter());
+        }
+    }
+
+    None
+}
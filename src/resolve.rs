@@ -0,0 +1,108 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::keys::*;
+use crate::layout::Layout;
+use crate::macros::MacroOutput;
+
+/// What a key or shortcut does in a layout.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Output<'a> {
+    /// Sends a key, e.g., `kp1` for `M` in the keypad layer.
+    Key(KeyLayer),
+    /// Performs an action, e.g., `mute`.
+    Action(Action),
+    /// Sends a key while holding what the shortcut's modifier keys send, e.g., `lctrl` and `left`
+    /// for `{lalt}{a}` if `lalt` is remapped to `lctrl` and `A` to `left`.
+    Shortcut(BTreeSet<KeyLayer>, KeyLayer),
+    /// Does nothing, i.e., a dead key.
+    Nothing,
+    /// Plays a macro.
+    Macro(&'a MacroOutput),
+}
+
+impl fmt::Display for Output<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Output::Key(key) => write!(f, "[{}]", key),
+            Output::Action(action) => write!(f, "[{}]", action),
+            Output::Shortcut(modifiers, key) => {
+                for modifier in modifiers.iter() {
+                    write!(f, "[{}]", modifier)?;
+                }
+
+                write!(f, "[{}]", key)
+            }
+            Output::Nothing => write!(f, "[null]"),
+            Output::Macro(macro_output) => write!(f, "{}", macro_output),
+        }
+    }
+}
+
+/// What `key` does in `layout`. A macro triggered by the key alone takes precedence over a
+/// remapping, and a key which is neither remapped nor a trigger sends its factory output.
+pub fn resolve<'a>(layout: &'a Layout, key: &KeyLayer) -> Output<'a> {
+    if let Some(non_modifier) = key.non_modifier() {
+        let shortcut = Shortcut {
+            keypad: key.keypad_state(),
+            modifiers: BTreeSet::new(),
+            non_modifier,
+        };
+
+        if let Some(macro_output) = layout.macros.get(&shortcut) {
+            return Output::Macro(macro_output);
+        }
+    }
+
+    match sends(layout, key) {
+        Some(Target::Key(output)) => Output::Key(output),
+        Some(Target::Action(action)) => Output::Action(action),
+        None => Output::Nothing,
+    }
+}
+
+/// What `shortcut` does in `layout`: its macro if it triggers one, otherwise its non-modifier
+/// key's output sent together with what its modifier keys send, as they are remapped. Dead
+/// modifier keys, and modifier keys remapped to an action, are left out. A shortcut whose
+/// non-modifier key is dead does nothing, and one whose non-modifier key is remapped to an action
+/// performs it.
+pub fn resolve_shortcut<'a>(layout: &'a Layout, shortcut: &Shortcut) -> Output<'a> {
+    if let Some(macro_output) = layout.macros.get(shortcut) {
+        return Output::Macro(macro_output);
+    }
+
+    let key = KeyLayer::new(shortcut.keypad, Key::NonModifier(shortcut.non_modifier));
+    let key = match sends(layout, &key) {
+        Some(Target::Key(key)) => key,
+        Some(Target::Action(action)) => return Output::Action(action),
+        None => return Output::Nothing,
+    };
+
+    let modifiers: BTreeSet<KeyLayer> = shortcut
+        .modifiers
+        .iter()
+        .filter_map(|&modifier| {
+            match sends(
+                layout,
+                &KeyLayer::new(shortcut.keypad, Key::Modifier(modifier)),
+            ) {
+                Some(Target::Key(key)) => Some(key),
+                _ => None,
+            }
+        })
+        .collect();
+
+    if modifiers.is_empty() {
+        Output::Key(key)
+    } else {
+        Output::Shortcut(modifiers, key)
+    }
+}
+
+/// What `key` sends in `layout`, ignoring macros, or `None` for a dead key.
+fn sends(layout: &Layout, key: &KeyLayer) -> Option<Target> {
+    match layout.remappings.get(key) {
+        Some(output) => output.as_ref().map(Target::factory_output),
+        None => Some(Target::Key(key.factory_output())),
+    }
+}
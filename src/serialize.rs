@@ -0,0 +1,53 @@
+//! `serde` support, enabled by the `serde` feature. Keys, shortcuts and macro output are
+//! serialized as the tokens used in layout files, e.g., `"kp-enter"` or `"{lctrl}{c}"`, so the
+//! serialized forms stay readable and do not depend on the order of enum variants.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+use crate::keys::*;
+use crate::macros::MacroOutput;
+
+macro_rules! serde_tokens {
+    ($($t:ty),*) => {
+        $(
+            impl Serialize for $t {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    serializer.collect_str(self)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $t {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    String::deserialize(deserializer)?
+                        .parse()
+                        .map_err(de::Error::custom)
+                }
+            }
+        )*
+    };
+}
+
+serde_tokens!(
+    Modifier,
+    NonModifier,
+    KeypadKey,
+    Action,
+    Key,
+    KeyLayer,
+    Target,
+    Shortcut,
+    MacroOutput
+);
+
+/// Serializes a `HashMap` in key order so that serializing a value always gives the same output.
+pub(crate) fn sorted<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    K: Ord + Serialize,
+    V: Serialize,
+    S: Serializer,
+{
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::parse::{ParseError, ParseErrorKind};
+use crate::vdrive::{write_file, LayoutSlot, ACTIVE_DIRECTORY};
+
+/// The name of the settings file within the v-drive's `active` directory.
+pub const SETTINGS_FILE_NAME: &str = "state.txt";
+
+/// Models the Advantage 2's settings file. Settings are written one per line as `name=value`.
+/// Settings not modelled here (e.g., those added by newer firmware) are kept in `other` so that
+/// reading and writing a settings file does not lose them.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct KeyboardSettings {
+    /// The layout loaded when the keyboard is powered on.
+    pub startup_file: LayoutSlot,
+    /// Whether a click sounds on every key press.
+    pub key_click: bool,
+    /// Whether a tone sounds when a toggle key such as caps lock is pressed.
+    pub toggle_tone: bool,
+    /// Whether macro playback is disabled.
+    pub macro_disable: bool,
+    /// Macro playback speed, from `0` (slowest) to `9` (fastest).
+    pub macro_speed: u8,
+    /// Playback speed of the status report, from `0` (slowest) to `9` (fastest).
+    pub status_play_speed: u8,
+    /// Whether the program key is locked to prevent on-board programming.
+    pub program_key_lock: bool,
+    /// Settings not modelled by the other fields, keyed by name.
+    pub other: BTreeMap<String, String>,
+}
+
+impl Default for KeyboardSettings {
+    /// The factory settings.
+    fn default() -> Self {
+        KeyboardSettings {
+            startup_file: LayoutSlot::Qwerty,
+            key_click: false,
+            toggle_tone: true,
+            macro_disable: false,
+            macro_speed: 3,
+            status_play_speed: 3,
+            program_key_lock: false,
+            other: BTreeMap::new(),
+        }
+    }
+}
+
+impl KeyboardSettings {
+    /// Parses a settings file. Settings missing from the file take their factory values.
+    pub fn parse(s: &str) -> Result<KeyboardSettings, ParseError> {
+        let mut settings = KeyboardSettings::default();
+
+        for (index, line) in s.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            settings.set(line).map_err(|kind| ParseError {
+                line: index + 1,
+                kind,
+            })?;
+        }
+
+        Ok(settings)
+    }
+
+    /// Writes the settings file into the v-drive mounted at `root`. Any file it replaces is
+    /// first backed up. Returns the path written.
+    pub fn write_to_vdrive(&self, root: &Path) -> io::Result<PathBuf> {
+        let path = root.join(ACTIVE_DIRECTORY).join(SETTINGS_FILE_NAME);
+        write_file(&path, &format!("{}", self))?;
+        Ok(path)
+    }
+
+    fn set(&mut self, line: &str) -> Result<(), ParseErrorKind> {
+        let (name, value) = line
+            .split_once('=')
+            .ok_or_else(|| ParseErrorKind::InvalidLine(line.to_string()))?;
+        let (name, value) = (name.trim(), value.trim());
+
+        let invalid = || ParseErrorKind::InvalidSetting(name.to_string(), value.to_string());
+
+        match name {
+            "startup_file" => {
+                self.startup_file = LayoutSlot::from_file_name(value).ok_or_else(invalid)?
+            }
+            "keyclick_status" => self.key_click = parse_switch(value).ok_or_else(invalid)?,
+            "toggle_status" => self.toggle_tone = parse_switch(value).ok_or_else(invalid)?,
+            "macro_disable" => self.macro_disable = parse_switch(value).ok_or_else(invalid)?,
+            "macro_speed" => self.macro_speed = parse_speed(value).ok_or_else(invalid)?,
+            "status_play_speed" => {
+                self.status_play_speed = parse_speed(value).ok_or_else(invalid)?
+            }
+            "program_key_lock" => {
+                self.program_key_lock = parse_switch(value).ok_or_else(invalid)?
+            }
+            _ => {
+                self.other.insert(name.to_string(), value.to_string());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for KeyboardSettings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut settings = vec![
+            format!("startup_file={}", self.startup_file.file_name()),
+            format!("keyclick_status={}", switch(self.key_click)),
+            format!("toggle_status={}", switch(self.toggle_tone)),
+            format!("macro_disable={}", switch(self.macro_disable)),
+            format!("macro_speed={}", self.macro_speed),
+            format!("status_play_speed={}", self.status_play_speed),
+            format!("program_key_lock={}", switch(self.program_key_lock)),
+        ];
+
+        for (name, value) in self.other.iter() {
+            settings.push(format!("{}={}", name, value));
+        }
+
+        write!(f, "{}", settings.join("\n"))
+    }
+}
+
+impl FromStr for KeyboardSettings {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        KeyboardSettings::parse(s)
+    }
+}
+
+fn switch(on: bool) -> &'static str {
+    if on {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+fn parse_switch(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "on" => Some(true),
+        "off" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_speed(value: &str) -> Option<u8> {
+    value.parse().ok().filter(|speed| *speed <= 9)
+}
@@ -0,0 +1,58 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::diff::LayoutDiff;
+use crate::layout::Layout;
+use crate::vdrive::{layout_path, LayoutSlot};
+
+/// How the layout in a v-drive slot compares with the layout it is expected to hold.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Status {
+    /// The slot holds the expected layout.
+    InSync,
+    /// The slot has no layout file.
+    Missing,
+    /// The slot holds a different layout, e.g., after keys were remapped on-board using the
+    /// program key. The diff treats the expected layout as the old one, so `Added` lists the
+    /// remappings and macros found only on the keyboard.
+    Drifted(LayoutDiff),
+}
+
+impl Status {
+    /// Indicates whether the slot holds the expected layout.
+    pub fn is_in_sync(&self) -> bool {
+        *self == Status::InSync
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Status::InSync => write!(f, "in sync"),
+            Status::Missing => write!(f, "missing"),
+            Status::Drifted(diff) => write!(f, "drifted:\n{}", diff),
+        }
+    }
+}
+
+/// Compares the layout in a slot of the v-drive mounted at `root` with `expected`. A layout file
+/// which cannot be parsed is returned as an `InvalidData` error.
+pub fn status(expected: &Layout, root: &Path, slot: LayoutSlot) -> io::Result<Status> {
+    let path = layout_path(root, slot);
+
+    if !path.exists() {
+        return Ok(Status::Missing);
+    }
+
+    let actual = Layout::parse(&fs::read_to_string(&path)?)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    let diff = expected.diff(&actual);
+
+    if diff.is_empty() {
+        Ok(Status::InSync)
+    } else {
+        Ok(Status::Drifted(diff))
+    }
+}
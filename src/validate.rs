@@ -0,0 +1,129 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::keys::*;
+use crate::layout::Layout;
+
+/// The firmware limits checked by `Layout::validate`. The `Default` value holds the Advantage 2's
+/// limits. Macro lengths are measured in tokens, i.e., key presses and releases such as `{a}` or
+/// `{-lshift}`, as this is what the keyboard stores.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Limits {
+    /// The maximum number of macros in a layout.
+    pub max_macros: usize,
+    /// The maximum length of a single macro.
+    pub max_macro_length: usize,
+    /// The maximum length of all the macros in a layout.
+    pub max_total_macro_length: usize,
+    /// Keys which the firmware does not allow to be remapped. None are listed by default: the
+    /// Advantage 2's Program and Keypad keys have no token, so a layout cannot name them, and an
+    /// action is never the key of a remapping.
+    pub fixed_keys: BTreeSet<KeyLayer>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_macros: 24,
+            max_macro_length: 300,
+            max_total_macro_length: 7200,
+            fixed_keys: BTreeSet::new(),
+        }
+    }
+}
+
+/// A problem which would prevent the keyboard from loading a layout as intended.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Hash, Debug)]
+pub enum Diagnostic {
+    TooManyMacros {
+        count: usize,
+        limit: usize,
+    },
+    MacroTooLong {
+        shortcut: Shortcut,
+        length: usize,
+        limit: usize,
+    },
+    TotalMacroLengthExceeded {
+        length: usize,
+        limit: usize,
+    },
+    FixedKeyRemapped(KeyLayer),
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::Diagnostic::*;
+
+        match self {
+            TooManyMacros { count, limit } => {
+                write!(f, "{} macros exceeds the limit of {}", count, limit)
+            }
+            MacroTooLong {
+                shortcut,
+                length,
+                limit,
+            } => write!(
+                f,
+                "macro for {} is {} tokens long, exceeding the limit of {}",
+                shortcut, length, limit
+            ),
+            TotalMacroLengthExceeded { length, limit } => write!(
+                f,
+                "macros are {} tokens long in total, exceeding the limit of {}",
+                length, limit
+            ),
+            FixedKeyRemapped(key) => write!(f, "[{}] cannot be remapped", key),
+        }
+    }
+}
+
+/// Checks a layout against `limits`, returning every problem found.
+pub fn validate(layout: &Layout, limits: &Limits) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    let mut remapped: Vec<&KeyLayer> = layout
+        .remappings
+        .keys()
+        .filter(|key| limits.fixed_keys.contains(key))
+        .collect();
+    remapped.sort_unstable();
+
+    for key in remapped {
+        diagnostics.push(Diagnostic::FixedKeyRemapped(key.clone()));
+    }
+
+    if layout.macros.len() > limits.max_macros {
+        diagnostics.push(Diagnostic::TooManyMacros {
+            count: layout.macros.len(),
+            limit: limits.max_macros,
+        });
+    }
+
+    let mut macros: Vec<_> = layout.macros.iter().collect();
+    macros.sort_unstable();
+
+    let mut total_length = 0;
+
+    for (shortcut, macro_output) in macros {
+        let length = macro_output.token_count();
+        total_length += length;
+
+        if length > limits.max_macro_length {
+            diagnostics.push(Diagnostic::MacroTooLong {
+                shortcut: shortcut.clone(),
+                length,
+                limit: limits.max_macro_length,
+            });
+        }
+    }
+
+    if total_length > limits.max_total_macro_length {
+        diagnostics.push(Diagnostic::TotalMacroLengthExceeded {
+            length: total_length,
+            limit: limits.max_total_macro_length,
+        });
+    }
+
+    diagnostics
+}
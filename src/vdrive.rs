@@ -0,0 +1,98 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The directory of the v-drive which holds the layout files the keyboard reads.
+pub const ACTIVE_DIRECTORY: &str = "active";
+
+/// The extension appended to a file's name when a backup is made before overwriting it.
+pub const BACKUP_EXTENSION: &str = "bak";
+
+/// The layout slots of the Advantage 2. Besides the built-in QWERTY and Dvorak layouts, up to
+/// nine custom layouts (`1` to `9`) can be stored and loaded with `Progm` and a number key.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Debug)]
+pub enum LayoutSlot {
+    Qwerty,
+    Dvorak,
+    Custom(u8),
+}
+
+impl LayoutSlot {
+    /// The name of the slot's layout file within the `active` directory.
+    pub fn file_name(self) -> String {
+        format!("{}.txt", self)
+    }
+
+    /// The slot whose layout file is named `file_name`, e.g., `qwerty.txt` or `1.txt`.
+    pub fn from_file_name(file_name: &str) -> Option<LayoutSlot> {
+        let name = file_name.strip_suffix(".txt")?;
+
+        match name.to_lowercase().as_str() {
+            "qwerty" => Some(LayoutSlot::Qwerty),
+            "dvorak" => Some(LayoutSlot::Dvorak),
+            n => n
+                .parse()
+                .ok()
+                .map(LayoutSlot::Custom)
+                .and_then(|s| s.check().ok()),
+        }
+    }
+
+    fn check(self) -> io::Result<LayoutSlot> {
+        match self {
+            LayoutSlot::Custom(n) if !(1..=9).contains(&n) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("custom layout slot {} is not between 1 and 9", n),
+            )),
+            slot => Ok(slot),
+        }
+    }
+}
+
+impl fmt::Display for LayoutSlot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LayoutSlot::Qwerty => write!(f, "qwerty"),
+            LayoutSlot::Dvorak => write!(f, "dvorak"),
+            LayoutSlot::Custom(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+/// The path of a slot's layout file on the v-drive mounted at `root`.
+pub fn layout_path(root: &Path, slot: LayoutSlot) -> PathBuf {
+    root.join(ACTIVE_DIRECTORY).join(slot.file_name())
+}
+
+/// Writes a layout, as produced by `Layout`'s `Display` implementation, into a slot on the
+/// v-drive mounted at `root` and returns the path written.
+pub fn write_layout(root: &Path, slot: LayoutSlot, layout: &str) -> io::Result<PathBuf> {
+    let path = layout_path(root, slot.check()?);
+    write_file(&path, layout)?;
+    Ok(path)
+}
+
+/// Writes `contents` to `path` using the firmware's `\r\n` line endings. An existing file is
+/// first copied to a backup alongside it, e.g., `qwerty.txt.bak`.
+pub fn write_file(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    if path.exists() {
+        fs::copy(path, backup_path(path))?;
+    }
+
+    let text: String = contents.lines().map(|l| format!("{}\r\n", l)).collect();
+
+    fs::write(path, text)
+}
+
+/// The path of the backup made before overwriting `path`.
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".");
+    file_name.push(BACKUP_EXTENSION);
+    path.with_file_name(file_name)
+}
@@ -0,0 +1,202 @@
+#![cfg(feature = "config")]
+
+extern crate kinesis_layout;
+extern crate tempfile;
+
+use std::fs;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use kinesis_layout::layout::*;
+use kinesis_layout::vdrive::*;
+
+fn kinesis_layout(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_kinesis-layout"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn build_test() {
+    let root = tempfile::tempdir().unwrap();
+    let config = root.path().join("layout.toml");
+    fs::write(&config, "[top]\nremap = { A = \"left\" }\n").unwrap();
+
+    let output = kinesis_layout(&[
+        "build",
+        "--config",
+        config.to_str().unwrap(),
+        "--remap",
+        "`=null",
+        "--remap",
+        "kp-enter=kp0",
+    ]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "[`]>[null]\n[A]>[left]\n[kp-enter]>[kp0]\n"
+    );
+
+    let output = kinesis_layout(&[
+        "build",
+        "--preset",
+        "colemak",
+        "--system",
+        "mac",
+        "--vdrive",
+        root.path().to_str().unwrap(),
+        "--slot",
+        "2",
+    ]);
+
+    assert!(output.status.success());
+
+    let written = fs::read_to_string(layout_path(root.path(), LayoutSlot::Custom(2))).unwrap();
+    let layout = Layout::parse(&written).unwrap();
+    assert_eq!(layout.remappings.len(), colemak().len() + 3);
+
+    fs::write(&config, "[top]\nremap = { T = \"left\" }\n").unwrap();
+    let output = kinesis_layout(&[
+        "build",
+        "--preset",
+        "colemak",
+        "--config",
+        config.to_str().unwrap(),
+    ]);
+
+    assert!(output.status.success());
+    let layout = Layout::parse(&String::from_utf8(output.stdout).unwrap()).unwrap();
+    assert_eq!(layout.remappings.len(), colemak().len());
+    assert!(layout.to_string().contains("[T]>[left]"));
+
+    fs::write(&config, "[top]\nremap = { A = \"nope\" }\n").unwrap();
+    let output = kinesis_layout(&["build", "--config", config.to_str().unwrap()]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .ends_with("line 2: unknown token `nope`\n"));
+}
+
+#[test]
+fn status_test() {
+    let root = tempfile::tempdir().unwrap();
+    let vdrive = root.path().to_str().unwrap();
+
+    let output = kinesis_layout(&["status", "--remap", "A=left", "--vdrive", vdrive]);
+    assert!(!output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "missing\n");
+
+    assert!(
+        kinesis_layout(&["build", "--remap", "A=left", "--vdrive", vdrive])
+            .status
+            .success()
+    );
+
+    let output = kinesis_layout(&["status", "--remap", "A=left", "--vdrive", vdrive]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "in sync\n");
+
+    let output = kinesis_layout(&["status", "--remap", "A=right", "--vdrive", vdrive]);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "drifted:\ntop layer:\n~ [A]>[right] => [A]>[left]\n"
+    );
+}
+
+/// Waits up to five seconds for the file at `path` to hold `contents`.
+fn wait_for(path: &Path, contents: &str) -> bool {
+    (0..100).any(|_| {
+        thread::sleep(Duration::from_millis(50));
+        fs::read_to_string(path).ok().as_deref() == Some(contents)
+    })
+}
+
+/// Kills the child process when dropped, so a failed assertion doesn't leave it running.
+struct KillOnDrop(Child);
+
+impl Drop for KillOnDrop {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+#[test]
+fn watch_test() {
+    let root = tempfile::tempdir().unwrap();
+    let config = root.path().join("layout.toml");
+    let output = root.path().join("layout.txt");
+    fs::write(&config, "[top]\nremap = { A = \"left\" }\n").unwrap();
+
+    let _watch = KillOnDrop(
+        Command::new(env!("CARGO_BIN_EXE_kinesis-layout"))
+            .args([
+                "watch",
+                "--config",
+                config.to_str().unwrap(),
+                "--output",
+                output.to_str().unwrap(),
+                "--interval",
+                "20",
+            ])
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap(),
+    );
+
+    assert!(wait_for(&output, "[A]>[left]\r\n"));
+
+    fs::write(&config, "[top]\nremap = { A = \"right\" }\n").unwrap();
+
+    assert!(wait_for(&output, "[A]>[right]\r\n"));
+    assert_eq!(
+        fs::read_to_string(backup_path(&output)).unwrap(),
+        "[A]>[left]\r\n"
+    );
+}
+
+#[test]
+fn watch_retry_test() {
+    let root = tempfile::tempdir().unwrap();
+    let config = root.path().join("layout.toml");
+    let blocker = root.path().join("out");
+    let output = blocker.join("layout.txt");
+    fs::write(&config, "[top]\nremap = { A = \"left\" }\n").unwrap();
+    fs::write(&blocker, "").unwrap();
+
+    let zero = kinesis_layout(&[
+        "watch",
+        "--config",
+        config.to_str().unwrap(),
+        "--interval",
+        "0",
+    ]);
+    assert!(!zero.status.success());
+
+    let _watch = KillOnDrop(
+        Command::new(env!("CARGO_BIN_EXE_kinesis-layout"))
+            .args([
+                "watch",
+                "--config",
+                config.to_str().unwrap(),
+                "--output",
+                output.to_str().unwrap(),
+                "--interval",
+                "20",
+            ])
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap(),
+    );
+
+    thread::sleep(Duration::from_millis(100));
+    fs::remove_file(&blocker).unwrap();
+
+    assert!(wait_for(&output, "[A]>[left]\r\n"));
+}
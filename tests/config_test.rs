@@ -0,0 +1,227 @@
+#![cfg(feature = "config")]
+
+extern crate indoc;
+#[macro_use]
+extern crate maplit;
+
+extern crate kinesis_layout;
+
+use indoc::indoc;
+
+use kinesis_layout::configure::*;
+use kinesis_layout::keys::*;
+use kinesis_layout::layout::*;
+use kinesis_layout::macros::*;
+use kinesis_layout::parse::*;
+
+#[test]
+fn from_toml_test() {
+    use Modifier::*;
+    use NonModifier::*;
+
+    let config = indoc!(
+        r#"
+        system = "mac"
+        preset = "colemak"
+
+        [top]
+        remap = { A = "left" }
+        dead_keys = ["`"]
+        remove = ["T"]
+        invert = ["hyphen"]
+
+        [keypad]
+        remap = { enter = "space" }
+
+        [cross_layer]
+        "kp-tab" = "A"
+
+        [keypad_keys]
+        toggle = ["caps"]
+
+        [[macro]]
+        trigger = "{kp-c}"
+        speed = 2
+        output = [
+            { text = "Hi" },
+            { delay = 100 },
+            { shortcut = "{lctrl}{c}" },
+            { command = "copy" },
+            { left = 2 },
+            { press = "lalt" },
+            { text = "\t" },
+            { release = "lalt" },
+        ]
+        "#
+    );
+
+    let expected: Layout = Configure::new()
+        .set_system(System::Mac)
+        .with_remappings(colemak())
+        .remove_remap(Key::NonModifier(T))
+        .remap(Key::NonModifier(A), Key::NonModifier(LeftArrow))
+        .dead_key(Key::NonModifier(Backtick))
+        .invert_key(Hyphen)
+        .remap_keypad(Key::NonModifier(Enter), Key::NonModifier(Space))
+        .remap_permissive(
+            KeyLayer::on(Key::NonModifier(Tab)),
+            KeyLayer::off(Key::NonModifier(A)),
+        )
+        .keypad_toggle(Key::NonModifier(CapsLock))
+        .with_macro(
+            Shortcut::keypad_on(btreeset! {}, C),
+            MacroBuilder::from_string("Hi")
+                .unwrap()
+                .speed(2)
+                .unwrap()
+                .delay(100)
+                .unwrap()
+                .with_shortcut(Shortcut::keypad_off(btreeset! {LeftControl}, C))
+                .with_command(Command::Copy)
+                .cursor_left(2)
+                .press(LeftAlt)
+                .with_string("\t")
+                .unwrap()
+                .release(LeftAlt)
+                .make(),
+        )
+        .make()
+        .unwrap();
+
+    assert_eq!(
+        Configure::from_toml(config).unwrap().make().unwrap(),
+        expected
+    );
+}
+
+#[test]
+fn from_toml_error_test() {
+    let error = |config: &str| Configure::from_toml(config).unwrap_err();
+
+    assert_eq!(
+        error("[top]\nremap = { A = \"nope\" }"),
+        ParseError {
+            line: 2,
+            kind: ParseErrorKind::UnknownToken("nope".to_string()),
+        }
+    );
+
+    assert_eq!(
+        error("preset = \"qwertz\""),
+        ParseError {
+            line: 1,
+            kind: ParseErrorKind::InvalidSetting("preset".to_string(), "qwertz".to_string()),
+        }
+    );
+
+    assert_eq!(
+        error("\n[[macro]]\ntrigger = \"{kp-c}\"\noutput = [\n    { text = \"é\" },\n]"),
+        ParseError {
+            line: 5,
+            kind: ParseErrorKind::InvalidMacro(MacroError::UnsupportedCharacter {
+                character: 'é',
+                position: 0,
+            }),
+        }
+    );
+
+    assert_eq!(
+        error("[[macro]]\ntrigger = \"{kp-c}\"\nspeed = 0\noutput = []"),
+        ParseError {
+            line: 3,
+            kind: ParseErrorKind::InvalidMacro(MacroError::InvalidSpeed(0)),
+        }
+    );
+
+    assert_eq!(
+        error("[[macro]]\ntrigger = \"{kp-c}\"\noutput = [{ press = \"lalt\" }]"),
+        ParseError {
+            line: 2,
+            kind: ParseErrorKind::InvalidMacro(MacroError::UnreleasedModifier(Modifier::LeftAlt)),
+        }
+    );
+
+    assert_eq!(
+        error("[keypad]\nremap = { A = \"left\" }\ndead_keys = [\"vol+\"]"),
+        ParseError {
+            line: 3,
+            kind: ParseErrorKind::RemappedAction(Action::VolumeUp),
+        }
+    );
+
+    assert_eq!(
+        error("[top]\nremap = { A = \"left\" }\ndead_keys = [\"a\"]"),
+        ParseError {
+            line: 3,
+            kind: ParseErrorKind::DuplicateRemapping(KeyLayer::off(Key::NonModifier(
+                NonModifier::A
+            ))),
+        }
+    );
+
+    assert_eq!(
+        error(indoc!(
+            r#"
+            [[macro]]
+            trigger = "{kp-c}"
+            output = [{ text = "a" }]
+
+            [[macro]]
+            trigger = "{kp-C}"
+            output = [{ text = "b" }]
+            "#
+        )),
+        ParseError {
+            line: 6,
+            kind: ParseErrorKind::DuplicateMacro(Shortcut::keypad_on(btreeset! {}, NonModifier::C)),
+        }
+    );
+
+    assert_eq!(
+        error(indoc!(
+            r#"
+            [top]
+            invert = ["1"]
+
+            [[macro]]
+            trigger = "{1}"
+            output = [{ text = "x" }]
+            "#
+        )),
+        ParseError {
+            line: 5,
+            kind: ParseErrorKind::DuplicateMacro(Shortcut::keypad_off(
+                btreeset! {},
+                NonModifier::One
+            )),
+        }
+    );
+
+    assert_eq!(
+        error(
+            "invert_numbers = true
+[top]
+invert = [\"2\"]"
+        ),
+        ParseError {
+            line: 1,
+            kind: ParseErrorKind::DuplicateMacro(Shortcut::keypad_off(
+                btreeset! {},
+                NonModifier::Two
+            )),
+        }
+    );
+
+    assert_eq!(
+        error("[top]\nremap = { kp1 = \"A\" }"),
+        ParseError {
+            line: 2,
+            kind: ParseErrorKind::InvalidConfig(
+                "keypad key `kp1` in [top]; remap it in [keypad]".to_string()
+            ),
+        }
+    );
+
+    assert_eq!(error("system = \"amiga\"\n").line, 1);
+    assert_eq!(error("\n\n[top]\nremapp = {}").line, 4);
+}
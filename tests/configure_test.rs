@@ -10,6 +10,9 @@ use kinesis_layout::configure::*;
 use kinesis_layout::keys::*;
 use kinesis_layout::layout::*;
 use kinesis_layout::macros::*;
+use kinesis_layout::merge::*;
+use kinesis_layout::parse::ParseErrorKind;
+use kinesis_layout::resolve::Output;
 
 #[test]
 fn layout_generation_test() {
@@ -25,6 +28,7 @@ fn layout_generation_test() {
         .with_macro(
             Shortcut::keypad_off(btreeset! {RightShift, LeftAlt}, T),
             MacroBuilder::from_string("www.test.com\nTHANKS")
+                .unwrap()
                 .cursor_left(6)
                 .make(),
         )
@@ -33,8 +37,10 @@ fn layout_generation_test() {
             MacroBuilder::new()
                 .with_command(Command::LineEnd)
                 .with_string("if  {\n")
+                .unwrap()
                 .cursor_down(1)
                 .with_string(" else  {\n")
+                .unwrap()
                 .cursor_up(3)
                 .with_shortcut(Shortcut::keypad_off(
                     btreeset! {LeftWindowsCommand},
@@ -43,7 +49,8 @@ fn layout_generation_test() {
                 .cursor_left(2)
                 .make(),
         )
-        .make();
+        .make()
+        .unwrap();
 
     let output =
     indoc!("[`]>[null]
@@ -89,3 +96,456 @@ fn layout_generation_test() {
 
     assert_eq!(format!("{}", layout), output);
 }
+
+#[test]
+fn from_layout_test() {
+    use Modifier::*;
+    use NonModifier::*;
+
+    let layout: Layout = Configure::new()
+        .set_system(System::Mac)
+        .with_remappings(colemak())
+        .dead_key(Key::NonModifier(Backtick))
+        .with_macro(
+            Shortcut::keypad_off(btreeset! {RightShift, LeftAlt}, T),
+            MacroBuilder::from_string("Hello")
+                .unwrap()
+                .with_command(Command::Copy)
+                .make(),
+        )
+        .make()
+        .unwrap();
+
+    assert_eq!(Configure::from_layout(&layout).make(), Ok(layout.clone()));
+
+    let edited = Configure::from_layout(&layout)
+        .remove_remap(Key::NonModifier(Backtick))
+        .remap(Key::NonModifier(A), Key::NonModifier(LeftArrow))
+        .make()
+        .unwrap();
+
+    assert_eq!(
+        edited
+            .remappings
+            .get(&KeyLayer::off(Key::NonModifier(Backtick))),
+        None
+    );
+    assert_eq!(
+        edited.remappings.get(&KeyLayer::off(Key::NonModifier(A))),
+        Some(&Some(Key::NonModifier(LeftArrow).into()))
+    );
+    assert_eq!(edited.macros, layout.macros);
+}
+
+#[test]
+fn macro_error_test() {
+    use NonModifier::*;
+
+    assert_eq!(
+        MacroBuilder::from_string("f(x) = 0")
+            .map(|b| b.make().to_macro_output(System::PC).unwrap()),
+        Ok(
+            "{f}{-lshift}{9}{+lshift}{x}{-lshift}{0}{+lshift}{space}{=}{space}{0}"
+                .parse()
+                .unwrap()
+        )
+    );
+
+    let error = MacroError::UnsupportedCharacter {
+        character: 'é',
+        position: 3,
+    };
+
+    assert_eq!(MacroBuilder::from_string("caf\u{e9}"), Err(error.clone()));
+
+    let shortcut = Shortcut::keypad_on(btreeset! {}, C);
+
+    assert_eq!(
+        Configure::new()
+            .try_with_macro(
+                shortcut.clone(),
+                MacroBuilder::from_string("caf\u{e9}").map(|b| b.make())
+            )
+            .make(),
+        Err(ConfigureError::Macro(shortcut, error))
+    );
+}
+
+#[test]
+fn merge_macro_error_test() {
+    let shortcut = Shortcut::keypad_on(btreeset! {}, NonModifier::C);
+
+    let mut base = Configure::new();
+    base.try_with_macro(
+        shortcut.clone(),
+        MacroBuilder::from_string("caf\u{e9}").map(|b| b.make()),
+    );
+
+    let mut personal = Configure::new();
+    personal.with_macro(
+        shortcut.clone(),
+        MacroBuilder::from_string("cafe").unwrap().make(),
+    );
+
+    let mut right = base.merge(&personal, MergePolicy::PreferRight).unwrap();
+    assert_eq!(right.conflicts, vec![Conflict::Macro(shortcut.clone())]);
+    assert!(right.merged.make().is_ok());
+
+    let mut left = base.merge(&personal, MergePolicy::PreferLeft).unwrap();
+    assert!(left.merged.make().is_err());
+
+    assert_eq!(
+        base.merge(&personal, MergePolicy::Error),
+        Err(MergeError {
+            conflicts: vec![Conflict::Macro(shortcut)]
+        })
+    );
+}
+
+#[test]
+fn action_test() {
+    use NonModifier::*;
+
+    let layout: Layout = Configure::new()
+        .remap_keypad(Key::NonModifier(F9), Action::Mute)
+        .remap_keypad(Key::NonModifier(F10), Action::VolumeDown)
+        .remap_keypad(Key::NonModifier(F11), Action::VolumeUp)
+        .remap(Key::NonModifier(CapsLock), Action::LeftClick)
+        .with_macro(
+            Shortcut::keypad_on(btreeset! {}, P),
+            MacroBuilder::from_string("ok")
+                .unwrap()
+                .with_action(Action::PlayPause)
+                .with_action(Action::NextTrack)
+                .make(),
+        )
+        .make()
+        .unwrap();
+
+    let output = indoc!(
+        "[caps]>[lmouse]
+        [kp-F9]>[mute]
+        [kp-F10]>[vol-]
+        [kp-F11]>[vol+]
+        {kpmin}>{o}{k}{play}{next}"
+    );
+
+    assert_eq!(format!("{}", layout), output);
+    assert_eq!(Layout::parse(output), Ok(layout.clone()));
+    assert_eq!(Configure::from_layout(&layout).make(), Ok(layout.clone()));
+
+    assert_eq!(
+        layout.resolve(&KeyLayer::on(Key::NonModifier(F9))),
+        Output::Action(Action::Mute)
+    );
+    assert_eq!(
+        format!(
+            "{}",
+            "{-lctrl}{lmouse}{+lctrl}".parse::<MacroOutput>().unwrap()
+        ),
+        "{-lctrl}{lmouse}{+lctrl}"
+    );
+}
+
+#[test]
+fn delay_test() {
+    use NonModifier::*;
+
+    let layout: Layout = Configure::new()
+        .with_macro(
+            Shortcut::keypad_on(btreeset! {}, R),
+            MacroBuilder::new()
+                .with_shortcut(Shortcut::keypad_off(
+                    btreeset! {Modifier::LeftWindowsCommand},
+                    R,
+                ))
+                .delay(500)
+                .unwrap()
+                .with_string("cmd\n")
+                .unwrap()
+                .speed(9)
+                .unwrap()
+                .speed(3)
+                .unwrap()
+                .make(),
+        )
+        .make()
+        .unwrap();
+
+    let output = "{kp-r}>{s3}{-lwin}{r}{+lwin}{d500}{c}{m}{d}{enter}";
+
+    assert_eq!(format!("{}", layout), output);
+    assert_eq!(Layout::parse(output), Ok(layout.clone()));
+    assert_eq!(Configure::from_layout(&layout).make(), Ok(layout));
+
+    assert_eq!(
+        MacroBuilder::new().delay(0).map(|b| b.make()),
+        Err(MacroError::InvalidDelay(0))
+    );
+    assert_eq!(
+        MacroBuilder::new().delay(1000).map(|b| b.make()),
+        Err(MacroError::InvalidDelay(1000))
+    );
+    assert_eq!(
+        MacroBuilder::new().speed(10).map(|b| b.make()),
+        Err(MacroError::InvalidSpeed(10))
+    );
+    assert_eq!(
+        "{a}{d1000}".parse::<MacroOutput>(),
+        Err(ParseErrorKind::InvalidMacro(MacroError::InvalidDelay(1000)))
+    );
+    assert_eq!(
+        "{a}{d70000}".parse::<MacroOutput>(),
+        Err(ParseErrorKind::InvalidMacro(MacroError::InvalidDelay(
+            70000
+        )))
+    );
+    assert_eq!(
+        "{s300}{a}".parse::<MacroOutput>(),
+        Err(ParseErrorKind::InvalidMacro(MacroError::InvalidSpeed(300)))
+    );
+    assert_eq!(
+        "{a}{d99999999999}".parse::<MacroOutput>(),
+        Err(ParseErrorKind::InvalidMacro(MacroError::InvalidDelay(
+            u32::MAX
+        )))
+    );
+    assert_eq!(
+        format!(
+            "{}",
+            "{-lctrl}{d100}{c}{+lctrl}".parse::<MacroOutput>().unwrap()
+        ),
+        "{-lctrl}{d100}{c}{+lctrl}"
+    );
+}
+
+#[test]
+fn hold_test() {
+    use Modifier::*;
+    use NonModifier::*;
+
+    let layout: Layout = Configure::new()
+        .with_macro(
+            Shortcut::keypad_on(btreeset! {}, S),
+            MacroBuilder::new()
+                .holding(btreeset! {LeftShift}, |b| {
+                    b.cursor_down(2);
+                    Ok(())
+                })
+                .unwrap()
+                .press(LeftAlt)
+                .with_string("\t\t")
+                .unwrap()
+                .release(LeftAlt)
+                .make(),
+        )
+        .make()
+        .unwrap();
+
+    let output = "{kp-s}>{-lshift}{down}{down}{+lshift}{-lalt}{tab}{tab}{+lalt}";
+
+    assert_eq!(format!("{}", layout), output);
+    assert_eq!(Layout::parse(output), Ok(layout));
+
+    let layout: Layout = Configure::new()
+        .with_macro(
+            Shortcut::keypad_on(btreeset! {}, S),
+            MacroBuilder::new()
+                .holding(btreeset! {LeftShift}, |b| {
+                    b.with_string("ab")?;
+                    Ok(())
+                })
+                .unwrap()
+                .press(LeftAlt)
+                .with_string("\t")
+                .unwrap()
+                .release(LeftAlt)
+                .make(),
+        )
+        .make()
+        .unwrap();
+
+    let output = "{kp-s}>{-lshift}{a}{b}{+lshift}{-lalt}{tab}{+lalt}";
+
+    assert_eq!(format!("{}", layout), output);
+    assert_eq!(Layout::parse(output), Ok(layout));
+
+    let layout: Layout = Configure::new()
+        .with_macro(
+            Shortcut::keypad_on(btreeset! {}, C),
+            MacroBuilder::new()
+                .holding(btreeset! {LeftControl}, |b| {
+                    b.with_command(Command::Copy).cursor_down(1);
+                    Ok(())
+                })
+                .unwrap()
+                .make(),
+        )
+        .with_macro(
+            Shortcut::keypad_on(btreeset! {}, V),
+            MacroBuilder::new()
+                .holding(btreeset! {LeftShift}, |b| {
+                    b.with_string("A")?.cursor_down(1);
+                    Ok(())
+                })
+                .unwrap()
+                .make(),
+        )
+        .make()
+        .unwrap();
+
+    let output = indoc!(
+        "{kp-c}>{-lctrl}{c}{down}{+lctrl}
+        {kp-v}>{-lshift}{a}{down}{+lshift}"
+    );
+
+    assert_eq!(format!("{}", layout), output);
+    assert_eq!(Layout::parse(output), Ok(layout));
+
+    assert_eq!(
+        MacroBuilder::new()
+            .press(LeftAlt)
+            .with_string("\t")
+            .unwrap()
+            .make()
+            .to_macro_output(System::PC),
+        Err(MacroError::UnreleasedModifier(LeftAlt))
+    );
+    assert_eq!(
+        "{-lctrl}{-lctrl}{c}{+lctrl}{down}{+lctrl}".parse::<MacroOutput>(),
+        Err(ParseErrorKind::InvalidMacro(
+            MacroError::UnreleasedModifier(LeftControl)
+        ))
+    );
+
+    let mut builder = MacroBuilder::from_string("a").unwrap();
+
+    assert_eq!(
+        builder
+            .holding(btreeset! {LeftControl}, |b| {
+                b.with_string("\u{e9}")?;
+                Ok(())
+            })
+            .map(|b| b.make()),
+        Err(MacroError::UnsupportedCharacter {
+            character: '\u{e9}',
+            position: 0,
+        })
+    );
+    assert_eq!(builder, MacroBuilder::from_string("a").unwrap());
+
+    let shortcut = Shortcut::keypad_on(btreeset! {}, C);
+
+    assert_eq!(
+        Configure::new()
+            .with_macro(shortcut.clone(), MacroBuilder::new().press(LeftAlt).make())
+            .make(),
+        Err(ConfigureError::Macro(
+            shortcut.clone(),
+            MacroError::UnreleasedModifier(LeftAlt)
+        ))
+    );
+    assert_eq!(
+        Configure::new()
+            .with_macro(
+                shortcut.clone(),
+                MacroBuilder::new().release(LeftAlt).make()
+            )
+            .make(),
+        Err(ConfigureError::Macro(
+            shortcut,
+            MacroError::UnpressedModifier(LeftAlt)
+        ))
+    );
+
+    assert_eq!(
+        "{-lalt}{tab}{tab}".parse::<MacroOutput>(),
+        Err(ParseErrorKind::InvalidMacro(
+            MacroError::UnreleasedModifier(LeftAlt)
+        ))
+    );
+    assert_eq!(
+        "{tab}{+lalt}".parse::<MacroOutput>(),
+        Err(ParseErrorKind::InvalidMacro(MacroError::UnpressedModifier(
+            LeftAlt
+        )))
+    );
+}
+
+#[test]
+fn keypad_key_test() {
+    use NonModifier::*;
+
+    let layout: Layout = Configure::new()
+        .remap_keypad(Key::NonModifier(J), Key::Keypad(KeypadKey::Kp7))
+        .remap_keypad(Key::Keypad(KeypadKey::Kp7), Key::NonModifier(J))
+        .remap(Key::NonModifier(CapsLock), Key::Keypad(KeypadKey::Plus))
+        .with_macro(
+            Shortcut::keypad_key(btreeset! {}, KeypadKey::Minus),
+            MacroBuilder::new()
+                .with_shortcut(Shortcut::keypad_key(btreeset! {}, KeypadKey::Kp0))
+                .make(),
+        )
+        .make()
+        .unwrap();
+
+    let output = indoc!(
+        "[caps]>[kpplus]
+        [kp4]>[kp7]
+        [kp7]>[kp4]
+        {kpmin}>{kp0}"
+    );
+
+    assert_eq!(format!("{}", layout), output);
+    assert_eq!(Layout::parse(output), Ok(layout));
+}
+
+#[test]
+fn keypad_layer_key_test() {
+    use Modifier::*;
+    use NonModifier::*;
+
+    let layout: Layout = Configure::new()
+        .keypad_shift(Key::Modifier(RightWindowsCommand))
+        .keypad_toggle(Key::NonModifier(CapsLock))
+        .make()
+        .unwrap();
+
+    let output = indoc!(
+        "[rwin]>[kpshft]
+        [caps]>[kptoggle]
+        [kp-rwin]>[kpshft]
+        [kp-caps]>[kptoggle]"
+    );
+
+    assert_eq!(format!("{}", layout), output);
+    assert_eq!(Layout::parse(output), Ok(layout));
+}
+
+#[test]
+fn pedal_test() {
+    use Modifier::*;
+    use NonModifier::*;
+
+    let layout: Layout = Configure::new()
+        .remap(Key::NonModifier(LeftPedal), Key::Modifier(LeftShift))
+        .keypad_shift(Key::NonModifier(MiddlePedal))
+        .keypad_dead_key(Key::NonModifier(RightPedal))
+        .with_macro(
+            Shortcut::keypad_off(btreeset! {LeftControl}, RightPedal),
+            MacroBuilder::new().with_command(Command::Paste).make(),
+        )
+        .make()
+        .unwrap();
+
+    let output = indoc!(
+        "[lp-tab]>[lshift]
+        [mp-kpshf]>[kpshft]
+        [kp-mp-kpshf]>[kpshft]
+        [kp-rp-kpent]>[null]
+        {lctrl}{rp-kpent}>{-lctrl}{v}{+lctrl}"
+    );
+
+    assert_eq!(format!("{}", layout), output);
+    assert_eq!(Layout::parse(output), Ok(layout));
+}
@@ -0,0 +1,53 @@
+extern crate kinesis_layout;
+
+use kinesis_layout::geometry::*;
+use kinesis_layout::info::*;
+use kinesis_layout::keys::*;
+
+#[test]
+fn geometry_test() {
+    use NonModifier::*;
+
+    let f = Key::NonModifier(F).geometry().unwrap();
+    let j = Key::NonModifier(J).geometry().unwrap();
+    assert_eq!((f.hand, f.cluster), (Hand::Left, Cluster::Main));
+    assert_eq!((f.width, f.height), (1.0, 1.0));
+    assert_eq!(j.hand, Hand::Right);
+    assert!(f.centre().0 < j.centre().0);
+    assert_eq!(f.centre().1, j.centre().1);
+
+    let g = Key::NonModifier(G).geometry().unwrap();
+    assert_eq!(f.distance(&g), 1.0);
+
+    let tab = Key::NonModifier(Tab).geometry().unwrap();
+    assert!(tab.width > 1.0);
+
+    let space = Key::NonModifier(Space).geometry().unwrap();
+    assert_eq!((space.cluster, space.height), (Cluster::Thumb, 2.0));
+
+    let escape = Key::NonModifier(Escape).geometry().unwrap();
+    assert_eq!(escape.cluster, Cluster::FunctionRow);
+    assert!(escape.height < 1.0);
+
+    assert_eq!(
+        Key::Keypad(KeypadKey::Kp1).geometry(),
+        Key::NonModifier(M).geometry()
+    );
+    assert_eq!(Key::NonModifier(LeftPedal).geometry(), None);
+
+    let (x, y) = f.centre();
+    assert_eq!(key_at(x, y), Some(Key::NonModifier(F)));
+    assert_eq!(key_at(-1.0, -1.0), None);
+}
+
+#[test]
+fn keys_do_not_overlap_test() {
+    let keys: Vec<(Key, Geometry)> = Key::all()
+        .filter_map(|key| key.geometry().map(|g| (key, g)))
+        .collect();
+
+    for (key, geometry) in keys.iter() {
+        let (x, y) = geometry.centre();
+        assert_eq!(key_at(x, y).as_ref(), Some(key));
+    }
+}
@@ -0,0 +1,69 @@
+extern crate kinesis_layout;
+
+use std::collections::BTreeSet;
+
+use kinesis_layout::info::*;
+use kinesis_layout::keys::*;
+
+#[test]
+fn all_keys_test() {
+    assert_eq!(Modifier::all().count(), 8);
+    assert_eq!(NonModifier::all().next(), Some(NonModifier::Escape));
+    assert_eq!(KeypadKey::all().count(), 19);
+    assert_eq!(Action::all().count(), 12);
+
+    let keys: Vec<Key> = Key::all().collect();
+    assert_eq!(
+        keys.len(),
+        Modifier::all().count() + NonModifier::all().count()
+    );
+
+    let key_layers: BTreeSet<KeyLayer> = KeyLayer::all().collect();
+    assert_eq!(key_layers.len(), 2 * keys.len() - 1);
+    assert!(key_layers.contains(&KeyLayer::on(Key::Keypad(KeypadKey::Kp1))));
+    assert!(!key_layers
+        .iter()
+        .any(|key| key.keypad_state() == Keypad::On
+            && *key.key() == Key::NonModifier(NonModifier::M)));
+
+    for key in key_layers {
+        assert_eq!(key.to_string().parse::<KeyLayer>(), Ok(key));
+    }
+}
+
+#[test]
+fn key_info_test() {
+    assert_eq!(
+        Key::NonModifier(NonModifier::F).info(),
+        Some(KeyInfo {
+            hand: Some(Hand::Left),
+            finger: Some(Finger::Index),
+            cluster: Cluster::Main,
+            row: 3,
+            column: 4,
+            legend: "F",
+        })
+    );
+
+    let quote = Key::NonModifier(NonModifier::Quote).info().unwrap();
+    assert_eq!(quote.hand, Some(Hand::Right));
+    assert_eq!(quote.finger, Some(Finger::Pinky));
+
+    let space = Key::NonModifier(NonModifier::Space).info().unwrap();
+    assert_eq!(space.cluster, Cluster::Thumb);
+    assert_eq!(space.finger, Some(Finger::Thumb));
+
+    let escape = Key::NonModifier(NonModifier::Escape).info().unwrap();
+    assert_eq!(escape.cluster, Cluster::FunctionRow);
+    assert_eq!(escape.legend, "Esc");
+
+    let pedal = Key::NonModifier(NonModifier::LeftPedal).info().unwrap();
+    assert_eq!((pedal.hand, pedal.finger), (None, None));
+    assert_eq!((pedal.cluster, pedal.legend), (Cluster::Pedal, "Tab"));
+
+    assert_eq!(
+        Key::Keypad(KeypadKey::Kp1).info(),
+        Key::NonModifier(NonModifier::M).info()
+    );
+    assert_eq!(Key::Modifier(Modifier::LeftWindowsCommand).info(), None);
+}
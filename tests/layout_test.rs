@@ -0,0 +1,296 @@
+extern crate indoc;
+#[macro_use]
+extern crate maplit;
+
+extern crate kinesis_layout;
+
+use indoc::indoc;
+
+use kinesis_layout::configure::*;
+use kinesis_layout::diff::*;
+use kinesis_layout::keys::*;
+use kinesis_layout::layout::*;
+use kinesis_layout::macros::*;
+use kinesis_layout::merge::*;
+use kinesis_layout::reachability::*;
+use kinesis_layout::resolve::*;
+use kinesis_layout::validate::*;
+
+#[test]
+fn layout_diff_test() {
+    use Modifier::*;
+    use NonModifier::*;
+
+    let old: Layout = Configure::new()
+        .remap(Key::NonModifier(S), Key::NonModifier(R))
+        .dead_key(Key::NonModifier(Backtick))
+        .remap_keypad(Key::NonModifier(Enter), Key::NonModifier(Space))
+        .with_macro(
+            Shortcut::keypad_off(btreeset! {LeftAlt}, T),
+            MacroBuilder::from_string("thanks").unwrap().make(),
+        )
+        .make()
+        .unwrap();
+
+    let new: Layout = Configure::new()
+        .remap(Key::NonModifier(S), Key::NonModifier(D))
+        .remap(Key::NonModifier(A), Key::NonModifier(LeftArrow))
+        .remap_keypad(Key::NonModifier(Enter), Key::NonModifier(Space))
+        .with_macro(
+            Shortcut::keypad_off(btreeset! {LeftAlt}, T),
+            MacroBuilder::from_string("Thanks").unwrap().make(),
+        )
+        .keypad_dead_key(Key::NonModifier(Tab))
+        .make()
+        .unwrap();
+
+    let diff = old.diff(&new);
+
+    assert_eq!(
+        diff.remappings_in(Keypad::Off).collect::<Vec<_>>(),
+        vec![
+            &Change::Removed(KeyLayer::off(Key::NonModifier(Backtick)), None),
+            &Change::Added(
+                KeyLayer::off(Key::NonModifier(A)),
+                Some(Key::NonModifier(LeftArrow).into())
+            ),
+            &Change::Changed(
+                KeyLayer::off(Key::NonModifier(S)),
+                Some(Key::NonModifier(R).into()),
+                Some(Key::NonModifier(D).into())
+            ),
+        ]
+    );
+
+    let output = indoc!(
+        "top layer:
+        - [`]>[null]
+        + [A]>[left]
+        ~ [S]>[R] => [S]>[D]
+        ~ {lalt}{t}>{t}{h}{a}{n}{k}{s} => {lalt}{t}>{-lshift}{t}{+lshift}{h}{a}{n}{k}{s}
+        keypad layer:
+        + [kp-tab]>[null]"
+    );
+
+    assert_eq!(format!("{}", diff), output);
+    assert!(new.diff(&new).is_empty());
+}
+
+#[test]
+fn layout_merge_test() {
+    use NonModifier::*;
+
+    let base: Layout = Configure::new()
+        .with_remappings(colemak())
+        .dead_key(Key::NonModifier(Backtick))
+        .make()
+        .unwrap();
+
+    let personal: Layout = Configure::new()
+        .remap(Key::NonModifier(S), Key::NonModifier(S))
+        .remap(Key::NonModifier(T), Key::NonModifier(G))
+        .remap(Key::NonModifier(A), Key::NonModifier(LeftArrow))
+        .make()
+        .unwrap();
+
+    let s = KeyLayer::off(Key::NonModifier(S));
+
+    let left = base.merge(&personal, MergePolicy::PreferLeft).unwrap();
+    assert_eq!(left.conflicts, vec![Conflict::Remapping(s.clone())]);
+    assert_eq!(left.merged.remappings[&s], Some(Key::NonModifier(R).into()));
+    assert_eq!(
+        left.merged.remappings[&KeyLayer::off(Key::NonModifier(A))],
+        Some(Key::NonModifier(LeftArrow).into())
+    );
+
+    let right = base.merge(&personal, MergePolicy::PreferRight).unwrap();
+    assert_eq!(right.merged.remappings[&s], Some(s.clone().into()));
+
+    assert_eq!(
+        base.merge(&personal, MergePolicy::Error),
+        Err(MergeError {
+            conflicts: vec![Conflict::Remapping(s)]
+        })
+    );
+}
+
+#[test]
+fn layout_validate_test() {
+    use NonModifier::*;
+
+    let layout: Layout = Configure::new()
+        .remap(Key::NonModifier(A), Key::NonModifier(LeftArrow))
+        .with_macro(
+            Shortcut::keypad_on(btreeset! {}, C),
+            MacroBuilder::from_string("Thanks").unwrap().make(),
+        )
+        .with_macro(
+            Shortcut::keypad_on(btreeset! {}, V),
+            MacroBuilder::from_string("thanks").unwrap().make(),
+        )
+        .make()
+        .unwrap();
+
+    assert_eq!(layout.validate(&Limits::default()), vec![]);
+
+    let limits = Limits {
+        max_macros: 1,
+        max_macro_length: 7,
+        max_total_macro_length: 10,
+        fixed_keys: btreeset! {KeyLayer::off(Key::NonModifier(A))},
+    };
+
+    assert_eq!(
+        layout.validate(&limits),
+        vec![
+            Diagnostic::FixedKeyRemapped(KeyLayer::off(Key::NonModifier(A))),
+            Diagnostic::TooManyMacros { count: 2, limit: 1 },
+            Diagnostic::MacroTooLong {
+                shortcut: Shortcut::keypad_on(btreeset! {}, C),
+                length: 8,
+                limit: 7,
+            },
+            Diagnostic::TotalMacroLengthExceeded {
+                length: 14,
+                limit: 10,
+            },
+        ]
+    );
+}
+
+#[test]
+fn layout_reachability_test() {
+    use NonModifier::*;
+
+    let a = KeyLayer::off(Key::NonModifier(A));
+    let left = KeyLayer::off(Key::NonModifier(LeftArrow));
+    let backtick = KeyLayer::off(Key::NonModifier(Backtick));
+    let enter = KeyLayer::on(Key::NonModifier(Enter));
+    let space = KeyLayer::on(Key::NonModifier(Space));
+
+    let layout: Layout = Configure::new()
+        .with_remappings(colemak())
+        .remap(Key::NonModifier(A), Key::NonModifier(LeftArrow))
+        .remap_keypad(Key::NonModifier(Enter), Key::NonModifier(Space))
+        .dead_key(Key::NonModifier(Backtick))
+        .make()
+        .unwrap();
+
+    let reachability = layout.reachability();
+
+    assert!(!reachability.is_permutation());
+    assert_eq!(
+        reachability.unreachable,
+        vec![backtick.clone(), a.clone(), enter.clone()]
+    );
+    assert_eq!(
+        reachability.duplicated,
+        btreemap! {
+            left.clone() => btreeset! {a.clone(), left.clone()},
+            space.clone() => btreeset! {enter.clone(), space.clone()},
+        }
+    );
+    assert_eq!(
+        reachability.suggestions,
+        vec![
+            Suggestion {
+                key: left,
+                output: a,
+            },
+            Suggestion {
+                key: space,
+                output: enter,
+            },
+        ]
+    );
+
+    let output = indoc!(
+        "top layer:
+        unreachable [`]
+        unreachable [A]
+        [left] produced by [A], [left]
+        suggest [left]>[A]
+        keypad layer:
+        unreachable [kp-enter]
+        [kp0] produced by [kp-enter], [kp0]
+        suggest [kp0]>[kp-enter]"
+    );
+
+    assert_eq!(format!("{}", reachability), output);
+
+    let colemak: Layout = Configure::new().with_remappings(colemak()).make().unwrap();
+    assert!(colemak.reachability().is_permutation());
+}
+
+#[test]
+fn layout_resolve_test() {
+    use Modifier::*;
+    use NonModifier::*;
+
+    let layout: Layout = Configure::new()
+        .remap(Key::NonModifier(A), Key::NonModifier(LeftArrow))
+        .dead_key(Key::NonModifier(Backtick))
+        .remap_keypad(Key::NonModifier(B), Key::NonModifier(Space))
+        .remap(Key::Modifier(LeftAlt), Key::Modifier(LeftControl))
+        .dead_key(Key::Modifier(RightAlt))
+        .with_macro(
+            Shortcut::keypad_off(btreeset! {}, T),
+            MacroBuilder::from_string("thanks").unwrap().make(),
+        )
+        .with_macro(
+            Shortcut::keypad_off(btreeset! {LeftAlt}, S),
+            MacroBuilder::from_string("sorry").unwrap().make(),
+        )
+        .make()
+        .unwrap();
+
+    let off = |key| KeyLayer::off(Key::NonModifier(key));
+    let on = |key| KeyLayer::on(Key::NonModifier(key));
+
+    assert_eq!(layout.resolve(&off(A)), Output::Key(off(LeftArrow)));
+    assert_eq!(layout.resolve(&off(Backtick)), Output::Nothing);
+    assert_eq!(layout.resolve(&off(S)), Output::Key(off(S)));
+    assert_eq!(
+        layout.resolve(&on(M)),
+        Output::Key(KeyLayer::on(Key::Keypad(KeypadKey::Kp1)))
+    );
+    assert_eq!(layout.resolve(&on(A)), Output::Key(off(A)));
+    assert_eq!(
+        layout.resolve(&on(B)),
+        Output::Key(KeyLayer::on(Key::Keypad(KeypadKey::Kp0)))
+    );
+    assert_eq!(format!("{}", layout.resolve(&off(T))), "{t}{h}{a}{n}{k}{s}");
+
+    assert_eq!(
+        format!(
+            "{}",
+            layout.resolve_shortcut(&Shortcut::keypad_off(btreeset! {LeftAlt}, S))
+        ),
+        "{s}{o}{r}{r}{y}"
+    );
+    assert_eq!(
+        layout.resolve_shortcut(&Shortcut::keypad_off(btreeset! {LeftAlt, LeftShift}, A)),
+        Output::Shortcut(
+            btreeset! {
+                KeyLayer::off(Key::Modifier(LeftControl)),
+                KeyLayer::off(Key::Modifier(LeftShift)),
+            },
+            off(LeftArrow)
+        )
+    );
+    assert_eq!(
+        format!(
+            "{}",
+            layout.resolve_shortcut(&Shortcut::keypad_off(btreeset! {LeftAlt}, A))
+        ),
+        "[lctrl][left]"
+    );
+    assert_eq!(
+        layout.resolve_shortcut(&Shortcut::keypad_off(btreeset! {RightAlt}, T)),
+        Output::Key(off(T))
+    );
+    assert_eq!(
+        layout.resolve_shortcut(&Shortcut::keypad_off(btreeset! {LeftAlt}, Backtick)),
+        Output::Nothing
+    );
+}
@@ -0,0 +1,209 @@
+extern crate indoc;
+#[macro_use]
+extern crate maplit;
+
+extern crate kinesis_layout;
+
+use indoc::indoc;
+
+use kinesis_layout::configure::*;
+use kinesis_layout::keys::*;
+use kinesis_layout::layout::*;
+use kinesis_layout::macros::*;
+use kinesis_layout::parse::*;
+
+use std::collections::BTreeSet;
+
+#[test]
+fn layout_round_trip_test() {
+    use Modifier::*;
+    use NonModifier::*;
+
+    let layout: Layout = Configure::new()
+        .with_remappings(colemak())
+        .remap(Key::NonModifier(A), Key::NonModifier(LeftArrow))
+        .remap_keypad(Key::NonModifier(Enter), Key::NonModifier(Space))
+        .dead_key(Key::NonModifier(Backtick))
+        .invert_numbers()
+        .with_macro(
+            Shortcut::keypad_on(BTreeSet::new(), C),
+            MacroBuilder::from_string("www.test.com, THANKS")
+                .unwrap()
+                .make(),
+        )
+        .with_macro(
+            Shortcut::keypad_off(btreeset! {RightShift, LeftAlt}, I),
+            MacroBuilder::new()
+                .with_command(Command::LineEnd)
+                .with_string("if  {\n")
+                .unwrap()
+                .with_shortcut(Shortcut::keypad_off(
+                    btreeset! {LeftWindowsCommand, LeftShift},
+                    RightArrow,
+                ))
+                .with_shortcut(Shortcut::keypad_on(BTreeSet::new(), K))
+                .cursor_left(2)
+                .make(),
+        )
+        .make()
+        .unwrap();
+
+    let output = format!("{}", layout);
+    let parsed: Layout = output.parse().unwrap();
+
+    assert_eq!(format!("{}", parsed), output);
+    assert_eq!(parsed, layout);
+}
+
+#[test]
+fn layout_parse_error_test() {
+    let layout = indoc!(
+        "[A]>[left]
+
+        [kp-enter]>[kp0]
+        {kp-c}>{w}{-lshift}{w}
+        "
+    );
+
+    assert_eq!(
+        Layout::parse(layout),
+        Err(ParseError {
+            line: 4,
+            kind: ParseErrorKind::InvalidMacro(MacroError::UnreleasedModifier(Modifier::LeftShift)),
+        })
+    );
+
+    assert_eq!(
+        Layout::parse("[A]>[left]\n[B]>[foo]"),
+        Err(ParseError {
+            line: 2,
+            kind: ParseErrorKind::UnknownToken("foo".to_string()),
+        })
+    );
+
+    assert_eq!(
+        Layout::parse("[A]>[left]\n[mute]>[a]"),
+        Err(ParseError {
+            line: 2,
+            kind: ParseErrorKind::RemappedAction(Action::Mute),
+        })
+    );
+}
+
+#[test]
+fn token_parse_test() {
+    use Modifier::*;
+    use NonModifier::*;
+
+    assert_eq!("kp5".parse(), Ok(KeyLayer::on(Key::NonModifier(K))));
+    assert_eq!(
+        "kp-enter".parse(),
+        Ok(KeyLayer::on(Key::NonModifier(Enter)))
+    );
+    assert_eq!(
+        "Hyphen".parse(),
+        Ok(KeyLayer::off(Key::NonModifier(Hyphen)))
+    );
+    assert_eq!("f11".parse(), Ok(Key::NonModifier(F11)));
+    assert_eq!("lalt".parse(), Ok(Key::Modifier(LeftAlt)));
+
+    for (key, token) in [
+        (Escape, "escape"),
+        (PrintScreen, "prtscr"),
+        (ScrollLock, "scroll"),
+        (Pause, "pause"),
+    ] {
+        assert_eq!(format!("{}", key), token);
+        assert_eq!(token.parse(), Ok(key));
+        assert_eq!(
+            format!("kp-{}", token).parse(),
+            Ok(KeyLayer::on(Key::NonModifier(key)))
+        );
+    }
+
+    assert_eq!(
+        "{rshift}{lalt}{t}".parse(),
+        Ok(Shortcut::keypad_off(btreeset! {RightShift, LeftAlt}, T))
+    );
+
+    let output: MacroOutput = "{-rshift}{1}{+rshift}".parse().unwrap();
+    assert_eq!(
+        output,
+        MacroBuilder::new()
+            .with_shortcut(Shortcut::keypad_off(btreeset! {RightShift}, One))
+            .make()
+            .to_macro_output(System::PC)
+            .unwrap()
+    );
+
+    assert_eq!("kpplus".parse(), Ok(Key::Keypad(KeypadKey::Plus)));
+    assert_eq!(KeypadKey::Kp1.physical_key(), M);
+    assert_eq!(
+        KeypadKey::from_physical_key(Seven),
+        Some(KeypadKey::NumLock)
+    );
+    assert_eq!(KeypadKey::from_physical_key(A), None);
+    assert_eq!(
+        KeyLayer::on(Key::NonModifier(M)),
+        KeyLayer::off(Key::Keypad(KeypadKey::Kp1))
+    );
+    assert_eq!(
+        KeyLayer::new(Keypad::Off, Key::Keypad(KeypadKey::Kp1)).keypad_state(),
+        Keypad::On
+    );
+    assert_eq!("mute".parse(), Ok(Target::Action(Action::Mute)));
+    assert_eq!(
+        "kp-a".parse(),
+        Ok(Target::Key(KeyLayer::on(Key::NonModifier(A))))
+    );
+    assert_eq!(
+        Target::from(Key::NonModifier(Space)).in_layer(Keypad::On),
+        Target::Key(KeyLayer::on(Key::NonModifier(Space)))
+    );
+    assert_eq!(
+        Target::Action(Action::Mute).in_layer(Keypad::On),
+        Target::Action(Action::Mute)
+    );
+    assert_eq!(
+        format!("{}", KeyLayer::on(Key::NonModifier(International))),
+        "kp-insert"
+    );
+    assert_eq!(
+        KeyLayer::on(Key::NonModifier(Insert)),
+        KeyLayer::on(Key::Keypad(KeypadKey::Insert))
+    );
+
+    let layout: Layout = Configure::new()
+        .remap_keypad(Key::NonModifier(Tab), Key::NonModifier(Insert))
+        .make()
+        .unwrap();
+
+    assert_eq!(format!("{}", layout), "[kp-tab]>[kp-insert]");
+    assert_eq!(Layout::parse("[kp-tab]>[kp-insert]"), Ok(layout));
+    assert_eq!(
+        "{kp-lctrl}{kp1}".parse(),
+        Ok(Shortcut::keypad_key(
+            btreeset! {LeftControl},
+            KeypadKey::Kp1
+        ))
+    );
+
+    let escape = Shortcut::keypad_off(btreeset! {LeftControl}, Escape);
+    assert_eq!(format!("{}", escape), "{lctrl}{escape}");
+    assert_eq!("{lctrl}{escape}".parse(), Ok(escape.clone()));
+
+    let output = MacroBuilder::new()
+        .with_shortcut(escape)
+        .with_shortcut(Shortcut::keypad_off(btreeset! {}, PrintScreen))
+        .make()
+        .to_macro_output(System::PC)
+        .unwrap();
+    assert_eq!(format!("{}", output), "{-lctrl}{escape}{+lctrl}{prtscr}");
+    let parsed: MacroOutput = format!("{}", output).parse().unwrap();
+    assert_eq!(format!("{}", parsed), format!("{}", output));
+
+    assert_eq!(
+        "{lshift}".parse::<Shortcut>(),
+        Err(ParseErrorKind::InvalidShortcut("{lshift}".to_string()))
+    );
+}
@@ -0,0 +1,114 @@
+#![cfg(feature = "serde")]
+
+#[macro_use]
+extern crate maplit;
+
+extern crate kinesis_layout;
+extern crate serde_json;
+
+use serde_json::json;
+
+use kinesis_layout::configure::*;
+use kinesis_layout::keys::*;
+use kinesis_layout::layout::*;
+use kinesis_layout::macros::*;
+
+#[test]
+fn layout_serde_test() {
+    use Modifier::*;
+    use NonModifier::*;
+
+    let layout: Layout = Configure::new()
+        .remap(Key::NonModifier(A), Key::NonModifier(LeftArrow))
+        .remap_keypad(Key::NonModifier(Enter), Key::NonModifier(Space))
+        .dead_key(Key::NonModifier(Backtick))
+        .with_macro(
+            Shortcut::keypad_off(btreeset! {LeftAlt}, T),
+            MacroBuilder::from_string("Hi").unwrap().make(),
+        )
+        .make()
+        .unwrap();
+
+    let value = serde_json::to_value(&layout).unwrap();
+
+    assert_eq!(
+        value,
+        json!({
+            "remappings": {"`": null, "A": "left", "kp-enter": "kp0"},
+            "macros": {"{lalt}{T}": "{-lshift}{h}{+lshift}{i}"}
+        })
+    );
+    assert_eq!(serde_json::from_value::<Layout>(value).unwrap(), layout);
+}
+
+#[test]
+fn configure_serde_test() {
+    use Modifier::*;
+    use NonModifier::*;
+
+    let mut configure = Configure::new();
+    configure
+        .set_system(System::Mac)
+        .remap(Key::Modifier(LeftAlt), Key::NonModifier(Delete))
+        .with_macro(
+            Shortcut::keypad_on(btreeset! {}, C),
+            MacroBuilder::from_string("a")
+                .unwrap()
+                .with_shortcut(Shortcut::keypad_off(btreeset! {LeftControl}, S))
+                .with_command(Command::JumpForward)
+                .make(),
+        );
+
+    let value = serde_json::to_value(&configure).unwrap();
+
+    assert_eq!(
+        value,
+        json!({
+            "system": "mac",
+            "remappings": {"lalt": "delete"},
+            "macros": {
+                "{kp-C}": [
+                    {"key_presses": [{"shifted": false, "key": "A"}]},
+                    {"shortcut": "{lctrl}{S}"},
+                    {"command": "jump_forward"}
+                ]
+            }
+        })
+    );
+    assert_eq!(
+        serde_json::from_value::<Configure>(value).unwrap(),
+        configure
+    );
+
+    configure.try_with_macro(
+        Shortcut::keypad_on(btreeset! {}, V),
+        MacroBuilder::from_string("caf\u{e9}").map(|b| b.make()),
+    );
+    assert!(serde_json::to_value(&configure).is_err());
+
+    let mut unbalanced: Configure = serde_json::from_value(json!({
+        "system": "pc",
+        "remappings": {},
+        "macros": {
+            "{kp-C}": [
+                {"press": "lalt"},
+                {"key_presses": [{"shifted": false, "key": "tab"}]}
+            ]
+        }
+    }))
+    .unwrap();
+
+    assert_eq!(
+        unbalanced.make(),
+        Err(ConfigureError::Macro(
+            Shortcut::keypad_on(btreeset! {}, C),
+            MacroError::UnreleasedModifier(LeftAlt)
+        ))
+    );
+
+    assert!(serde_json::from_value::<Key>(json!("nope")).is_err());
+    assert_eq!(
+        serde_json::from_value::<KeyLayer>(json!("kp-insert")).unwrap(),
+        KeyLayer::on(Key::NonModifier(International))
+    );
+}
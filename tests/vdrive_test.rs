@@ -0,0 +1,122 @@
+extern crate kinesis_layout;
+extern crate tempfile;
+
+use std::fs;
+
+use kinesis_layout::configure::*;
+use kinesis_layout::diff::*;
+use kinesis_layout::keys::*;
+use kinesis_layout::parse::*;
+use kinesis_layout::settings::*;
+use kinesis_layout::status::*;
+use kinesis_layout::vdrive::*;
+
+#[test]
+fn write_to_vdrive_test() {
+    use NonModifier::*;
+
+    let root = tempfile::tempdir().unwrap();
+
+    let path = Configure::new()
+        .remap(Key::NonModifier(A), Key::NonModifier(LeftArrow))
+        .dead_key(Key::NonModifier(Backtick))
+        .write_to_vdrive(root.path(), LayoutSlot::Custom(1))
+        .unwrap();
+
+    assert_eq!(path, root.path().join("active").join("1.txt"));
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "[`]>[null]\r\n[A]>[left]\r\n"
+    );
+
+    let layout = Configure::new()
+        .remap(Key::NonModifier(B), Key::NonModifier(C))
+        .make()
+        .unwrap();
+    layout
+        .write_to_vdrive(root.path(), LayoutSlot::Custom(1))
+        .unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "[B]>[C]\r\n");
+    assert_eq!(
+        fs::read_to_string(backup_path(&path)).unwrap(),
+        "[`]>[null]\r\n[A]>[left]\r\n"
+    );
+
+    assert!(layout
+        .write_to_vdrive(root.path(), LayoutSlot::Custom(10))
+        .is_err());
+}
+
+#[test]
+fn settings_test() {
+    let root = tempfile::tempdir().unwrap();
+
+    let settings: KeyboardSettings =
+        "startup_file=2.txt\r\nkeyclick_status=on\r\ntoggle_status=off\r\nmacro_speed=9\r\nv_drive=off\r\n"
+            .parse()
+            .unwrap();
+
+    assert_eq!(settings.startup_file, LayoutSlot::Custom(2));
+    assert!(settings.key_click);
+    assert!(!settings.toggle_tone);
+    assert!(!settings.other.contains_key("toggle_status"));
+    assert_eq!(settings.macro_speed, 9);
+    assert_eq!(settings.status_play_speed, 3);
+
+    let path = settings.write_to_vdrive(root.path()).unwrap();
+    let written = fs::read_to_string(path).unwrap();
+
+    assert_eq!(written.parse(), Ok(settings));
+    assert!(written.contains("toggle_status=off\r\n"));
+    assert!(!written.contains("toggle_tone"));
+    assert!(written.ends_with("v_drive=off\r\n"));
+
+    assert_eq!(
+        KeyboardSettings::parse("toggle_status=on\nmacro_speed=10"),
+        Err(ParseError {
+            line: 2,
+            kind: ParseErrorKind::InvalidSetting("macro_speed".to_string(), "10".to_string()),
+        })
+    );
+}
+
+#[test]
+fn status_test() {
+    use NonModifier::*;
+
+    let root = tempfile::tempdir().unwrap();
+    let slot = LayoutSlot::Custom(3);
+
+    let layout = Configure::new()
+        .remap(Key::NonModifier(A), Key::NonModifier(LeftArrow))
+        .make()
+        .unwrap();
+
+    assert_eq!(layout.status(root.path(), slot).unwrap(), Status::Missing);
+
+    layout.write_to_vdrive(root.path(), slot).unwrap();
+    assert!(layout.status(root.path(), slot).unwrap().is_in_sync());
+
+    let path = layout_path(root.path(), slot);
+    fs::write(&path, "[A]>[left]\r\n[B]>[C]\r\n").unwrap();
+
+    let status = layout.status(root.path(), slot).unwrap();
+    assert_eq!(
+        status,
+        Status::Drifted(LayoutDiff {
+            remappings: vec![Change::Added(
+                KeyLayer::off(Key::NonModifier(B)),
+                Some(Key::NonModifier(C).into())
+            )],
+            macros: vec![],
+        })
+    );
+    assert_eq!(format!("{}", status), "drifted:\ntop layer:\n+ [B]>[C]");
+
+    fs::write(&path, "[A]>left\r\n").unwrap();
+    assert_eq!(
+        layout.status(root.path(), slot).unwrap_err().kind(),
+        std::io::ErrorKind::InvalidData
+    );
+}